@@ -0,0 +1,135 @@
+//! Checks the raw files without starting the game.
+//!
+//! `polymorph-raws` loads every raw file the game would, lists any problems and prints a table of
+//! the items. It exits with an error if there were problems, so it can be run before committing.
+//! `polymorph-raws schema` instead prints a JSON Schema of the raw files, which editors can use to
+//! check and complete them as they are written.
+//!
+//! Run it from the directory the game runs in, so `raws/` and `mods/` are found.
+
+// serde's derives expand to impls and cfgs that newer compilers flag; nothing we can change from here.
+#![allow(non_local_definitions, unexpected_cfgs)]
+// House style, as in the game itself.
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::single_match)]
+
+use std::process;
+use schemars::schema::{Schema, SchemaObject, InstanceType, SingleOrVec};
+use simplelog::{TermLogger, LevelFilter, Config, TerminalMode};
+
+use polymorph::raw::{self, RawMaster, RawError, Raws};
+
+const USAGE : &str = "Usage: polymorph-raws [check | schema] [--verbose]
+
+  check     Load the raws, list any problems and print a table of the items (default)
+  schema    Print a JSON Schema of the raw files
+  --verbose Also show which files and mods are loaded";
+
+fn main () {
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|arg| arg == "--verbose");
+    let commands : Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+
+    let filter = if verbose { LevelFilter::Info } else { LevelFilter::Warn };
+    match TermLogger::init(filter, Config::default(), TerminalMode::Stderr) {
+        Ok(_v) => {},
+        Err(e) => eprintln!("Unable to start logging : {}", e),
+    }
+
+    let code = match commands.as_slice() {
+        [] => check(),
+        [command] if command.as_str() == "check" => check(),
+        [command] if command.as_str() == "schema" => schema(),
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        },
+    };
+    process::exit(code);
+}
+
+/// Loads the raws and reports on them. Returns the exit code.
+fn check () -> i32 {
+    let mut raws = RawMaster::empty();
+    let problems = match raws.load_raws() {
+        Ok(_v) => Vec::new(),
+        Err(RawError::Invalid(problems)) => problems,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        },
+    };
+
+    print_table(&raws);
+    if problems.is_empty() {
+        println!("\n{} item(s), no problems found.", raws.raws.items.len());
+        return 0;
+    }
+    eprintln!("\n{} problem(s) found:", problems.len());
+    for problem in problems.iter() {
+        eprintln!("  {}", problem);
+    }
+    return 1;
+}
+
+fn print_table (raws : &RawMaster) {
+    let id_width = raws.raws.items.iter().map(|item| item.id.len()).max().unwrap_or(0).max(2);
+    let name_width = raws.raws.items.iter().map(|item| item.name.len()).max().unwrap_or(0).max(4);
+    println!("{:<id_width$}  {:<name_width$}  {:<12}  {:>8}  {:>10}  {:>6}  Source", "Id", "Name", "Type", "Weight", "Value", "Rarity", id_width = id_width, name_width = name_width);
+    println!("{}", "-".repeat(id_width + name_width + 54));
+    for item in raws.raws.items.iter() {
+        let source = raws.get_item_origin(raw::item_id(&item.id)).map(|origin| origin.source.as_str()).unwrap_or("");
+        println!("{:<id_width$}  {:<name_width$}  {:<12}  {:>8.2}  {:>10.2}  {:>6.2}  {}", item.id, item.name, item.item_type.name(), item.weight, item.value, item.rarity, source, id_width = id_width, name_width = name_width);
+    }
+}
+
+/// Prints the schema of a raw file. Returns the exit code.
+fn schema () -> i32 {
+    let mut schema = schemars::schema_for!(Raws);
+
+    //a patch only needs the id of the item it changes and the fields it changes
+    let patch = match schema.definitions.get("ItemRaw") {
+        Some(Schema::Object(item)) => {
+            let mut patch = item.clone();
+            let object = patch.object();
+            object.required.clear();
+            object.required.insert("id".to_string());
+            object.required.insert("patch".to_string());
+            object.properties.insert("patch".to_string(), Schema::Object(SchemaObject {
+                instance_type : Option::from(SingleOrVec::Single(Box::new(InstanceType::Boolean))),
+                enum_values : Option::from(vec![serde_json::Value::Bool(true)]),
+                ..Default::default()
+            }));
+            patch.metadata().description = Option::from("Changes only the given fields of an item from an earlier file.".to_string());
+            patch
+        },
+        _ => {
+            eprintln!("The item schema is missing");
+            return 1;
+        },
+    };
+    schema.definitions.insert("ItemPatch".to_string(), Schema::Object(patch));
+    match schema.schema.object().properties.get_mut("items") {
+        Some(Schema::Object(items)) => {
+            items.array().items = Option::from(SingleOrVec::Single(Box::new(Schema::Object(SchemaObject {
+                subschemas : Option::from(Box::new(schemars::schema::SubschemaValidation {
+                    any_of : Option::from(vec![
+                        Schema::new_ref("#/definitions/ItemRaw".to_string()),
+                        Schema::new_ref("#/definitions/ItemPatch".to_string()),
+                    ]),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }))));
+        },
+        _ => {},
+    }
+
+    match serde_json::to_string_pretty(&schema) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("Unable to write the schema : {}", e);
+            return 1;
+        },
+    }
+    return 0;
+}
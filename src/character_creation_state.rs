@@ -0,0 +1,154 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, Rect, ColorPair, RGB, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime};
+use crate::components::basic::Actor;
+use crate::components::gui::{Panel, Justification};
+use crate::config::Settings;
+use crate::tileset::{Tileset, SpriteMap};
+use crate::raw::SharedRaws;
+use crate::locale::SharedStrings;
+use crate::test_state::TestState;
+use crate::level_generation::map::SeedCode;
+use crate::widgets::{Label, ListView};
+
+/// String table keys of the stats, in list order.
+const STAT_KEYS : [&str; 5] = ["creation.strength", "creation.dexterity", "creation.constitution", "creation.wisdom", "creation.intelligence"];
+const BASE_STAT : u8 = 8;
+const MIN_STAT : u8 = 3;
+const MAX_STAT : u8 = 18;
+const STARTING_POINTS : u8 = 10;
+
+/// Lets the player spread a pool of points over the `Actor` stats before the game starts.
+pub struct CharacterCreationState {
+    settings : Settings,
+    map_tileset : Tileset,
+    raws : SharedRaws,
+    strings : SharedStrings,
+    seed : SeedCode,
+    actor : Actor,
+    points : u8,
+    stats : ListView,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl CharacterCreationState {
+    pub fn new (settings : Settings, map_tileset : Tileset, raws : SharedRaws, strings : SharedStrings, seed : SeedCode) -> Self {
+        let mut actor = Actor::new();
+        actor.strength = BASE_STAT;
+        actor.dexterity = BASE_STAT;
+        actor.constitution = BASE_STAT;
+        actor.wisdom = BASE_STAT;
+        actor.intelligence = BASE_STAT;
+
+        let mut state = CharacterCreationState {
+            settings : settings,
+            map_tileset : map_tileset,
+            raws : raws,
+            strings : strings,
+            seed : seed,
+            actor : actor,
+            points : STARTING_POINTS,
+            stats : ListView::new(Vec::new()),
+            draw_batch : DrawBatch::new(),
+        };
+        state.refresh_stats();
+        return state;
+    }
+
+    fn stat_mut (&mut self, idx : usize) -> &mut u8 {
+        match idx {
+            0 => &mut self.actor.strength,
+            1 => &mut self.actor.dexterity,
+            2 => &mut self.actor.constitution,
+            3 => &mut self.actor.wisdom,
+            _ => &mut self.actor.intelligence,
+        }
+    }
+
+    fn refresh_stats (&mut self) {
+        let values = [self.actor.strength, self.actor.dexterity, self.actor.constitution, self.actor.wisdom, self.actor.intelligence];
+        self.stats.items = STAT_KEYS.iter().zip(values.iter()).map(|(key, value)| format!("{:<13} < {:>2} >", self.strings.get(key), value)).collect();
+    }
+
+    fn stats_area (ctx : &BTerm) -> Panel {
+        let screen_size = ctx.get_char_size();
+        return Panel::new(Rect::with_size(screen_size.0 as i32 / 2 - 10, screen_size.1 as i32 / 2 - 2, 20, 5), false, Justification::CENTER, Option::None);
+    }
+}
+
+impl State for CharacterCreationState {
+
+    fn init (&mut self) {}
+
+    fn on_enter (&mut self) {}
+
+    fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        let selected = self.stats.selected;
+        match input.key {
+            Some(VirtualKeyCode::Escape) => {
+                return StateAction::Pop(Option::from("Cancelled character creation.".to_string()));
+            },
+            Some(VirtualKeyCode::Return) => {
+                let mut actor = self.actor.clone();
+                actor.max_health = 50 + actor.constitution as i32 * 5;
+                actor.current_health = actor.max_health;
+                let game = TestState::new(ctx, &self.settings, self.raws.clone(), self.strings.clone(), SpriteMap::new(&self.map_tileset), self.seed.clone(), actor);
+                return StateAction::Switch(Box::new(game), Option::from(format!("Starting game with seed code {}.", self.seed)));
+            },
+            Some(VirtualKeyCode::Right) | Some(VirtualKeyCode::Equals) | Some(VirtualKeyCode::Add) => {
+                let points = self.points;
+                let stat = self.stat_mut(selected);
+                if points > 0 && *stat < MAX_STAT {
+                    *stat += 1;
+                    self.points -= 1;
+                }
+            },
+            Some(VirtualKeyCode::Left) | Some(VirtualKeyCode::Minus) | Some(VirtualKeyCode::Subtract) => {
+                let stat = self.stat_mut(selected);
+                if *stat > MIN_STAT {
+                    *stat -= 1;
+                    self.points += 1;
+                }
+            },
+            _ => {
+                self.stats.update(&input, &CharacterCreationState::stats_area(ctx));
+            },
+        }
+        self.refresh_stats();
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let screen_size = ctx.get_char_size();
+        let text_color = ColorPair::new(RGB::from_u8(255, 255, 255), RGB::from_u8(0, 0, 0));
+
+        self.draw_batch.target(0);
+        self.draw_batch.cls();
+        self.draw_batch.target(1);
+        self.draw_batch.cls();
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 - 6, self.strings.get("creation.title"), text_color);
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 - 4, self.strings.format("creation.points", &[&self.points]), text_color);
+        self.stats.draw(&mut self.draw_batch, &CharacterCreationState::stats_area(ctx));
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 + 4, self.strings.format("creation.health", &[&(50 + self.actor.constitution as i32 * 5)]), text_color);
+        Label::new(self.strings.format("creation.seed_code", &[&self.seed, &self.strings.map_type_name(self.seed.map_type)])).draw(&mut self.draw_batch, &Panel::new(Rect::with_size(1, 1, screen_size.0 as i32 - 2, 1), false, Justification::FREE, Option::None));
+        Label::new(self.strings.get("creation.help")).draw(&mut self.draw_batch, &Panel::new(Rect::with_size(1, screen_size.1 as i32 - 2, screen_size.0 as i32 - 2, 1), false, Justification::FREE, Option::None));
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+}
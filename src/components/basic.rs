@@ -1,15 +1,15 @@
-use specs::{Component, VecStorage, DenseVecStorage};
+use specs::{Component, VecStorage, DenseVecStorage, FlaggedStorage};
 use bracket_lib::prelude::RGB;
 
 use std::collections::HashMap;
 
-use crate::systems::render::ObjectShader;
+use crate::systems::render::{ObjectShader, RenderLayer};
 
-use crate::raw::ItemRaw;
-use crate::raw::{RAW};
+use crate::raw::{ItemRaw, RawMaster, EquipSlot};
+use crate::tileset::SpriteMap;
 
 #[derive(Debug, PartialEq, Component)]
-#[storage(DenseVecStorage)]
+#[storage(FlaggedStorage)]
 pub struct Position {
     pub x : i32,
     pub y : i32,
@@ -35,6 +35,7 @@ pub struct Renderable {
     pub fg_shader : ObjectShader,
     pub bg_shader : ObjectShader,
     pub shading : RGB,
+    pub layer : RenderLayer,
 }
 
 impl Renderable {
@@ -46,6 +47,7 @@ impl Renderable {
             fg_shader : fg_shader,
             bg_shader : bg_shader,
             shading : RGB::from_f32(0.0, 0.0, 0.0),
+            layer : RenderLayer::Ground,
         }
     }
 
@@ -57,9 +59,21 @@ impl Renderable {
             fg_shader : fg_shader,
             bg_shader : bg_shader,
             shading : RGB::from_f32(0.0, 0.0, 0.0),
+            layer : RenderLayer::Ground,
         }
     }
 
+    /// Makes a renderable from a named sprite, resolved for the active tileset.
+    pub fn new_from_sprite (sprites : &SpriteMap, name : &str, foreground_color : RGB, background_color : RGB, fg_shader : ObjectShader, bg_shader : ObjectShader) -> Self {
+        return Renderable::new(sprites.resolve(name), foreground_color, background_color, fg_shader, bg_shader);
+    }
+
+    /// Sets the layer this renderable is drawn on. Renderables default to the ground layer.
+    pub fn with_layer (mut self, layer : RenderLayer) -> Self {
+        self.layer = layer;
+        return self;
+    }
+
     pub fn get_shaded_foreground (&self) -> RGB {
         let shaded_foreground : RGB;
 
@@ -106,28 +120,59 @@ impl Renderable {
 pub struct Inventory {
     pub items : HashMap<u32, u32>,
     pub money : f32,
+    /// Id of the equipped weapon.
+    pub equipped : Option<u32>,
     size : usize,
 }
 
+impl Default for Inventory {
+    fn default () -> Self {
+        return Inventory::new();
+    }
+}
+
 impl Inventory {
     pub fn new () -> Self {
         Inventory {
             items : HashMap::new(),
             money : 0.0,
+            equipped : Option::None,
             size : 0,
         }
     }
 
-    pub fn add_item (&mut self, item : ItemRaw) {
-        let name = item.name.clone();
-        let id = RAW.lock().unwrap().get_item_id(name);
-        if self.items.contains_key(&id) {
-            self.items.entry(id).and_modify(|amt| *amt += 1);
-        } else {
-            self.items.insert(id, 1);
-        }
+    /// Adds one of the item to the inventory. Returns false if the item isn't in the raws.
+    pub fn add_item (&mut self, raws : &RawMaster, item : ItemRaw) -> bool {
+        let id = match raws.get_item_id(&item.id) {
+            Some(id) => id,
+            None => {
+                error!("No item found with the id \"{}\"", item.id);
+                return false;
+            },
+        };
+        *self.items.entry(id).or_insert(0) += 1;
         self.size += 1;
+        return true;
+    }
+
+    /// Takes one of the given item out of the inventory. Returns false if there was none.
+    pub fn remove_item (&mut self, id : u32) -> bool {
+        match self.items.get_mut(&id) {
+            Some(amt) => {
+                *amt -= 1;
+                if *amt == 0 {
+                    self.items.remove(&id);
+                    if self.equipped == Option::from(id) {
+                        self.equipped = Option::None;
+                    }
+                }
+                self.size -= 1;
+                return true;
+            },
+            None => return false,
+        }
     }
+
     pub fn get_size (&self) -> usize {
         return self.size;
     }
@@ -153,7 +198,21 @@ pub struct Currency {
     pub amt : u32,
 }
 
+/// An item that can be equipped.
 #[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+pub struct Equipable {
+    pub slot : EquipSlot,
+}
+
+/// An item that is used up when used, applying its effects.
+#[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+pub struct Consumable {
+    pub effects : Vec<String>,
+}
+
+#[derive(Component, Debug, Clone)]
 #[storage(VecStorage)]
 pub struct Actor {
     pub strength : u8,
@@ -165,6 +224,12 @@ pub struct Actor {
     pub current_health : i32,
 }
 
+impl Default for Actor {
+    fn default () -> Self {
+        return Actor::new();
+    }
+}
+
 impl Actor {
     pub fn new () -> Self {
         Actor {
@@ -177,6 +242,13 @@ impl Actor {
             current_health : 100,
         }
     }
+
+    /// Lowers health by `amount`. Returns true if this killed the actor.
+    pub fn take_damage (&mut self, amount : i32) -> bool {
+        let was_alive = self.current_health > 0;
+        self.current_health -= amount;
+        return was_alive && self.current_health <= 0;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -220,9 +292,7 @@ impl ColorLerp {
         self.accumulator += delta;
         if self.accumulator / self.rate >= 1.0 {
             self.accumulator = 0.0;
-            let temp = self.color_a;
-            self.color_a = self.color_b;
-            self.color_b = temp;
+            std::mem::swap(&mut self.color_a, &mut self.color_b);
         }
     }
 
@@ -1,12 +1,14 @@
-use specs::{Component, VecStorage, DenseVecStorage};
+use specs::{Component, VecStorage};
 use bracket_lib::prelude::{Rect, Point};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Justification {
     RIGHT,
     LEFT,
     CENTER,
     FREE,
+    /// Docked along the bottom, between any panels docked left and right.
+    BOTTOM,
 }
 
 #[derive(Component, Debug)]
@@ -47,6 +49,45 @@ impl Panel {
     pub fn height (&self) -> i32 {
         return self.bounds.height();
     }
+
+    /// Area inside the panel's border that widgets may draw into.
+    pub fn content (&self) -> Rect {
+        if self.decorations {
+            return Rect::with_exact(self.bounds.x1 + 1, self.bounds.y1 + 1, self.bounds.x2, self.bounds.y2 - 1);
+        }
+        return self.bounds;
+    }
+
+    /// Creates an undecorated panel placed relative to this panel's content area, clipped to it.
+    pub fn child (&self, relative : Rect) -> Panel {
+        let content = self.content();
+        let bounds = Rect::with_exact(
+            (content.x1 + relative.x1).min(content.x2),
+            (content.y1 + relative.y1).min(content.y2),
+            (content.x1 + relative.x2).min(content.x2),
+            (content.y1 + relative.y2).min(content.y2),
+        );
+        return Panel::new(bounds, false, Justification::FREE, Option::from(content));
+    }
+
+    /// A one line high child panel spanning the content area.
+    pub fn row (&self, y : i32) -> Panel {
+        let width = self.content().width();
+        return self.child(Rect::with_size(0, y, width, 1));
+    }
+
+    /// Docks the panel to a side of its container, keeping its size. Free and centered panels keep their position.
+    pub fn justify (&mut self, justification : Justification, container : Rect) {
+        let width = self.width();
+        let height = self.height();
+        match justification {
+            Justification::RIGHT => self.bounds = Rect::with_size(container.x2 - (width + 1), container.y1, width, height),
+            Justification::LEFT => self.bounds = Rect::with_size(container.x1, container.y1, width, height),
+            Justification::BOTTOM => self.bounds = Rect::with_size(container.x1, container.y2 - height, container.width() - 1, height),
+            _ => {},
+        }
+        self.justification = justification;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -55,6 +96,12 @@ pub struct PlayerCard {
     pub justification : Justification,
 }
 
+impl Default for PlayerCard {
+    fn default () -> Self {
+        return PlayerCard::new();
+    }
+}
+
 impl PlayerCard {
     pub fn new () -> Self {
         PlayerCard {
@@ -63,13 +110,19 @@ impl PlayerCard {
     }
 
     pub fn cycle_justification (&mut self) {
-        let new_justification : Justification;
-        match self.justification {
-            Justification::RIGHT => new_justification = Justification::LEFT,
-            Justification::LEFT => new_justification = Justification::FREE,
-            _ => new_justification = Justification::RIGHT,
-        }
-        self.justification = new_justification;
+        self.justification = match self.justification {
+            Justification::RIGHT => Justification::LEFT,
+            Justification::LEFT => Justification::FREE,
+            _ => Justification::RIGHT,
+        };
     }
 }
 
+
+/// Panel showing the most recent messages of the `GameLog`.
+#[derive(Component, Debug, Default)]
+#[storage(VecStorage)]
+pub struct MessageLogCard {
+    /// How many messages back from the newest the panel is scrolled.
+    pub scroll : usize,
+}
@@ -6,4 +6,14 @@ pub struct PlayerTag;
 
 #[derive(Component, Default)]
 #[storage(NullStorage)]
-pub struct DirtyTag;
\ No newline at end of file
+pub struct DirtyTag;
+
+/// Marks entities that other actors can't walk through.
+#[derive(Component, Default)]
+#[storage(NullStorage)]
+pub struct BlocksTile;
+
+/// Marks entities that attack the player. Seeing one interrupts travel.
+#[derive(Component, Default)]
+#[storage(NullStorage)]
+pub struct Hostile;
\ No newline at end of file
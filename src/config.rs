@@ -3,7 +3,12 @@ use toml;
 use std::io::Write;
 use std::fs::File;
 use std::fs;
-use bracket_lib::prelude::BACKEND;
+use std::path::Path;
+use std::collections::BTreeMap;
+use bracket_lib::prelude::{BTerm, BACKEND};
+use crate::level_generation::map::MapType;
+use crate::tileset::{Tileset, default_tilesets};
+use crate::locale::DEFAULT_LANGUAGE;
 
 /// Wrapper for user settings. Includes the serialized structure of user settings from the config file as well as log and debugging information.
 pub struct SettingsContext {
@@ -14,36 +19,122 @@ pub struct SettingsContext {
 }
 
 ///Structure for user settings
-#[derive(Deserialize, Default, Serialize)]
+#[derive(Deserialize, Default, Serialize, Clone)]
 pub struct Settings {
     pub development : Development,
     pub other : Other,
     pub graphical : Graphical,
+    #[serde(default)]
+    pub keybindings : KeyBindingConfig,
 }
 
-#[derive(Deserialize, Serialize)]
+/// Key bindings. Commands listed in `bindings` replace the preset's keys for that command.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct KeyBindingConfig {
+    /// One of "default", "vi" or "numpad".
+    pub preset : String,
+    /// Chords per command name, e.g. `pick_up = ["G", "shift+Comma"]`.
+    #[serde(default)]
+    pub bindings : BTreeMap<String, Vec<String>>,
+}
+
+impl Default for KeyBindingConfig {
+    fn default() -> Self {
+        KeyBindingConfig {
+            preset : "default".to_string(),
+            bindings : BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Other {
     pub screenshot_location : String,
+    /// Auto-explore walks to and picks up items it sees instead of stopping at them.
+    #[serde(default)]
+    pub auto_explore_pickup : bool,
+    /// Language of the string table in `lang/` to show text in.
+    #[serde(default = "default_language")]
+    pub language : String,
 }
 
 impl Default for Other {
     fn default() -> Self {
         Other {
             screenshot_location : "/screenshots".to_string(),
+            auto_explore_pickup : false,
+            language : default_language(),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Deserialize, Serialize, Default, Clone)]
 pub struct Development {
     pub debug : bool,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Graphical {
     pub fullscreen : bool,
     pub vsync : bool,
+    /// Name of the tileset the map is drawn with.
+    #[serde(default = "default_tileset")]
+    pub tileset : String,
+    /// Name of the tileset GUI text is drawn with. Should be a CP437 tileset.
+    #[serde(default = "default_tileset")]
+    pub text_font : String,
     pub post_processing : PostProcessing,
+    #[serde(default)]
+    pub daylight : Daylight,
+    #[serde(default = "default_tilesets")]
+    pub tilesets : Vec<Tileset>,
+    #[serde(default)]
+    pub console : ConsoleSettings,
+}
+
+/// Smallest console the GUI can be laid out in.
+pub const MIN_CONSOLE_SIZE : (u32, u32) = (40, 20);
+
+/// Size of the consoles and of the tiles drawn in them.
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub struct ConsoleSettings {
+    /// Fit as many tiles as the screen holds, and refit when the window is resized.
+    pub auto : bool,
+    /// Console size in tiles when `auto` is off or the screen size can't be found.
+    pub width : u32,
+    pub height : u32,
+    /// Size of a tile on screen in pixels. 0 uses the size of the map tileset's font.
+    pub tile_width : u32,
+    pub tile_height : u32,
+}
+
+impl Default for ConsoleSettings {
+    fn default() -> Self {
+        ConsoleSettings {
+            auto : true,
+            width : 80,
+            height : 40,
+            tile_width : 0,
+            tile_height : 0,
+        }
+    }
+}
+
+impl ConsoleSettings {
+    /// The configured tile size, or the font's where none is set.
+    pub fn tile_size (&self, tileset : &Tileset) -> (u32, u32) {
+        let width = if self.tile_width > 0 { self.tile_width } else { tileset.tile_width };
+        let height = if self.tile_height > 0 { self.tile_height } else { tileset.tile_height };
+        return (width, height);
+    }
+}
+
+fn default_language () -> String {
+    return DEFAULT_LANGUAGE.to_string();
+}
+
+fn default_tileset () -> String {
+    return "terminal".to_string();
 }
 
 impl Default for Graphical {
@@ -51,35 +142,184 @@ impl Default for Graphical {
         Graphical {
             fullscreen : true,
             vsync : false,
+            tileset : default_tileset(),
+            text_font : default_tileset(),
             post_processing : Default::default(),
+            daylight : Default::default(),
+            tilesets : default_tilesets(),
+            console : Default::default(),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Deserialize, Serialize, Default, Clone)]
 pub struct PostProcessing {
     pub scan_lines : bool,
     pub screen_burn : bool,
 }
 
-/// Auto-detects the resolution of the monitor and returns dimensions of console in tiles, depending on the resolution.
-pub fn auto_detect_resolution () -> (u32, u32) {
-    //grab the resolution of the current monitor from the backend
-    let monitor_size = BACKEND.lock().context_wrapper.as_ref().unwrap().wc.window().current_monitor().size();
-    let resolution : (u32, u32) = (monitor_size.width, monitor_size.height);
-    info!("Detected screen resolution: {} x {}", resolution.0, resolution.1);
-    
-    //convert the resolution of the monitor to tile dimensions
-    let mut width = resolution.0 / 24;
-    let mut height = resolution.1 /24;
-    let width_remainder = width % 10;
-    let height_remainder = height % 10;
-    
-    width = width - width_remainder;
-    height = height - height_remainder;
-    info!("Setting console tile resolution to: {} x {}", width, height);
+/// Schedule for sky light. Colors are hex strings, the same as in the raw files.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Daylight {
+    /// Number of turns in a full day.
+    pub day_length : u32,
+    /// Fraction of the day the game starts at; 0.0 is midnight and 0.5 is noon.
+    pub start_time : f32,
+    pub dawn : SkyPhase,
+    pub day : SkyPhase,
+    pub dusk : SkyPhase,
+    pub night : SkyPhase,
+    /// Biomes that light covered tiles with a fixed color instead of leaving them dark.
+    pub overrides : Vec<BiomeLighting>,
+}
 
-    return (width, height);
+impl Default for Daylight {
+    fn default() -> Self {
+        Daylight {
+            day_length : 1200,
+            start_time : 0.3,
+            dawn : SkyPhase::new("#ff9a5c", 0.35),
+            day : SkyPhase::new("#fff4e0", 0.8),
+            dusk : SkyPhase::new("#c2548a", 0.3),
+            night : SkyPhase::new("#2a3a7a", 0.08),
+            overrides : vec![
+                BiomeLighting::new(MapType::Hell, "#ff2a00", 0.25),
+                BiomeLighting::new(MapType::Cavern, "#000000", 0.0),
+                BiomeLighting::new(MapType::MushroomCavern, "#000000", 0.0),
+                BiomeLighting::new(MapType::Hive, "#000000", 0.0),
+            ],
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SkyPhase {
+    pub color : String,
+    pub intensity : f32,
+}
+
+impl SkyPhase {
+    pub fn new (color : &str, intensity : f32) -> Self {
+        SkyPhase {
+            color : color.to_string(),
+            intensity : intensity,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct BiomeLighting {
+    pub map_type : MapType,
+    pub color : String,
+    pub intensity : f32,
+}
+
+impl BiomeLighting {
+    pub fn new (map_type : MapType, color : &str, intensity : f32) -> Self {
+        BiomeLighting {
+            map_type : map_type,
+            color : color.to_string(),
+            intensity : intensity,
+        }
+    }
+}
+
+/// Asks the window for the resolution of the monitor it is on, in logical pixels. Returns `None` if there is no window
+/// yet or the platform doesn't report a size.
+pub fn detect_resolution () -> Option<(u32, u32)> {
+    let backend = BACKEND.lock();
+    let context_wrapper = match backend.context_wrapper.as_ref() {
+        Some(context_wrapper) => context_wrapper,
+        None => {
+            warn!("Unable to query the monitor: the window hasn't been created");
+            return Option::None;
+        },
+    };
+    let monitor = context_wrapper.wc.window().current_monitor();
+    let monitor_size = monitor.size();
+    if monitor_size.width == 0 || monitor_size.height == 0 {
+        warn!("Unable to query the monitor: it reported a size of {} x {}", monitor_size.width, monitor_size.height);
+        return Option::None;
+    }
+    info!("Detected screen resolution: {} x {}", monitor_size.width, monitor_size.height);
+    //the window and tiles are measured in logical pixels
+    let scale = monitor.scale_factor().max(1.0);
+    return Option::from(((monitor_size.width as f64 / scale) as u32, (monitor_size.height as f64 / scale) as u32));
+}
+
+/// Keeps both consoles sized to the window as it is resized.
+pub struct ConsoleLayout {
+    pub settings : ConsoleSettings,
+    pub tile_size : (u32, u32),
+    /// Window size in pixels the consoles were last fitted to.
+    window_size : (u32, u32),
+}
+
+impl ConsoleLayout {
+    pub fn new (settings : ConsoleSettings, tile_size : (u32, u32)) -> Self {
+        ConsoleLayout {
+            settings : settings,
+            tile_size : tile_size,
+            window_size : (0, 0),
+        }
+    }
+
+    /// Size of the consoles in tiles for a screen of the given size in pixels. Falls back to the
+    /// configured size when sizing isn't automatic or the screen size is unknown.
+    pub fn console_size (&self, resolution : Option<(u32, u32)>) -> (u32, u32) {
+        match resolution {
+            Some(resolution) if self.settings.auto => {
+                let width = (resolution.0 / self.tile_size.0.max(1)).max(MIN_CONSOLE_SIZE.0);
+                let height = (resolution.1 / self.tile_size.1.max(1)).max(MIN_CONSOLE_SIZE.1);
+                return (width, height);
+            },
+            _ => return (self.settings.width.max(MIN_CONSOLE_SIZE.0), self.settings.height.max(MIN_CONSOLE_SIZE.1)),
+        }
+    }
+
+    /// Sets both consoles to the given size.
+    pub fn resize_consoles (&self, ctx : &mut BTerm, size : (u32, u32)) {
+        let active = ctx.active_console;
+        for console in 0..2 {
+            ctx.set_active_console(console);
+            ctx.set_char_size(size.0, size.1);
+        }
+        ctx.set_active_console(active);
+        info!("Setting console tile resolution to: {} x {}", size.0, size.1);
+    }
+
+    /// Switches to new console settings and refits the consoles to the window. Returns the new
+    /// console size if it changed.
+    pub fn apply (&mut self, ctx : &mut BTerm, settings : ConsoleSettings) -> Option<(u32, u32)> {
+        self.settings = settings;
+        let size = self.console_size(Option::from((ctx.width_pixels, ctx.height_pixels)));
+        if size == ctx.get_char_size() {
+            return Option::None;
+        }
+        self.resize_consoles(ctx, size);
+        return Option::from(size);
+    }
+
+    /// Refits the consoles if the window changed size. Returns the new console size if it changed.
+    pub fn update (&mut self, ctx : &mut BTerm) -> Option<(u32, u32)> {
+        let window_size = (ctx.width_pixels, ctx.height_pixels);
+        if window_size == self.window_size {
+            return Option::None;
+        }
+        let first_fit = self.window_size == (0, 0);
+        self.window_size = window_size;
+        // the window starts out at the builder's size; the consoles were already fitted to the monitor
+        if first_fit || !self.settings.auto {
+            return Option::None;
+        }
+
+        let size = self.console_size(Option::from(window_size));
+        if size == ctx.get_char_size() {
+            return Option::None;
+        }
+        self.resize_consoles(ctx, size);
+        return Option::from(size);
+    }
 }
 
 ///Attempts to load user settings from the config.toml file. If the file is not found, a new config file, with default values, will be created.
@@ -96,7 +336,10 @@ pub fn load_config_file () -> SettingsContext {
         },
         Err(e) => {
             config_msg = format!("Unable to find config file: {}; Making a new one with default settings", e);
-            create_new_config_file(Default::default());
+            match create_new_config_file(Default::default()) {
+                Ok(_v) => {},
+                Err(e) => config_msg = format!("{}; {}", config_msg, e),
+            }
             log_warning = true;
         },
     }
@@ -127,9 +370,114 @@ pub fn load_config_file () -> SettingsContext {
     }
 }
 
-///Creates a new config file from a given Settings structure. 
-pub fn create_new_config_file (settings : Settings) {
-    let config_data = toml::to_string(&settings).unwrap();
-    let mut new_file = File::create("config.toml").unwrap();
-    write!(new_file, "{}", config_data).unwrap();
-}
\ No newline at end of file
+///Writes the given Settings structure to the config file. Keys in an existing config file that the game
+///doesn't know about are kept.
+pub fn create_new_config_file (settings : Settings) -> Result<(), String> {
+    return write_config(Path::new("config.toml"), settings);
+}
+
+fn write_config (path : &Path, settings : Settings) -> Result<(), String> {
+    // going through a string keeps f32 values such as 0.35 from being written out as 0.3499999940395355
+    let mut config = match toml::to_string(&settings).map(|t| t.parse::<toml::Value>()) {
+        Ok(Ok(t)) => t,
+        Ok(Err(e)) => return Err(format!("Unable to serialize settings : {}", e)),
+        Err(e) => return Err(format!("Unable to serialize settings : {}", e)),
+    };
+
+    match fs::read_to_string(path) {
+        Ok(existing) => {
+            match existing.parse::<toml::Value>() {
+                Ok(mut existing) => {
+                    merge_config(&mut existing, config, "");
+                    config = existing;
+                },
+                Err(e) => {
+                    warn!("Existing config file is invalid and will be replaced : {}", e);
+                },
+            }
+        },
+        Err(_e) => {},
+    }
+
+    let config_data = match toml::to_string(&config) {
+        Ok(t) => t,
+        Err(e) => return Err(format!("Unable to serialize settings : {}", e)),
+    };
+    let mut new_file = match File::create(path) {
+        Ok(t) => t,
+        Err(e) => return Err(format!("Unable to create config file : {}", e)),
+    };
+    match write!(new_file, "{}", config_data) {
+        Ok(_v) => return Ok(()),
+        Err(e) => return Err(format!("Unable to write config file : {}", e)),
+    }
+}
+
+/// Tables written out whole instead of merged, so entries removed in the game are removed from the file too.
+const REPLACED_TABLES : [&str; 1] = ["keybindings.bindings"];
+
+/// Copies the values of `settings` over `existing`, leaving keys only found in `existing` alone.
+/// Tables are merged key by key unless listed in `REPLACED_TABLES`; anything else, including arrays, is replaced.
+fn merge_config (existing : &mut toml::Value, settings : toml::Value, path : &str) {
+    match (existing, settings) {
+        (toml::Value::Table(existing), toml::Value::Table(settings)) if !REPLACED_TABLES.contains(&path) => {
+            for (key, value) in settings {
+                let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match existing.get_mut(&key) {
+                    Some(current) => merge_config(current, value, &key_path),
+                    None => {
+                        existing.insert(key, value);
+                    },
+                }
+            }
+        },
+        (existing, settings) => *existing = settings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consoles_fit_the_screen_when_automatic () {
+        let settings = ConsoleSettings { auto : true, width : 100, height : 50, ..ConsoleSettings::default() };
+        let layout = ConsoleLayout::new(settings.clone(), (16, 16));
+        assert_eq!(layout.console_size(Option::from((1920, 1080))), (120, 67));
+        // never smaller than the minimum, and the configured size when the screen is unknown
+        assert_eq!(layout.console_size(Option::from((320, 200))), MIN_CONSOLE_SIZE);
+        assert_eq!(layout.console_size(Option::None), (100, 50));
+
+        let fixed = ConsoleLayout::new(ConsoleSettings { auto : false, width : 10, height : 60, ..settings }, (16, 16));
+        assert_eq!(fixed.console_size(Option::from((1920, 1080))), (MIN_CONSOLE_SIZE.0, 60));
+
+        // a tile size of 0 doesn't divide by zero
+        let unsized_tiles = ConsoleLayout::new(ConsoleSettings { auto : true, ..ConsoleSettings::default() }, (0, 0));
+        assert_eq!(unsized_tiles.console_size(Option::from((80, 50))), (80, 50));
+    }
+
+    #[test]
+    fn removed_bindings_stay_removed_after_saving () {
+        let path = std::env::temp_dir().join(format!("polymorph-config-{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut settings = Settings::default();
+        settings.keybindings.bindings.insert("pick_up".to_string(), vec!["G".to_string()]);
+        settings.keybindings.bindings.insert("toggle_look".to_string(), vec!["L".to_string()]);
+        write_config(&path, settings.clone()).unwrap();
+
+        //a key the game doesn't know about, added by hand
+        let edited = format!("{}\n[modding]\nenabled = true\n", fs::read_to_string(&path).unwrap());
+        fs::write(&path, edited).unwrap();
+
+        settings.keybindings.bindings.remove("pick_up");
+        write_config(&path, settings).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let reloaded : Settings = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.keybindings.bindings.get("pick_up"), Option::None);
+        assert_eq!(reloaded.keybindings.bindings.get("toggle_look"), Option::from(&vec!["L".to_string()]));
+        assert!(saved.contains("[modding]"));
+    }
+}
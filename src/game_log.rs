@@ -0,0 +1,158 @@
+//! In-game message log.
+
+use bracket_lib::prelude::RGB;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum LogCategory {
+    Pickup,
+    Damage,
+    Status,
+    Discovery,
+    System,
+}
+
+impl LogCategory {
+    /// Color messages of this category are printed in.
+    pub fn color (&self) -> RGB {
+        match self {
+            LogCategory::Pickup => RGB::from_u8(238, 202, 0),
+            LogCategory::Damage => RGB::from_u8(230, 60, 60),
+            LogCategory::Status => RGB::from_u8(200, 120, 255),
+            LogCategory::Discovery => RGB::from_u8(90, 200, 255),
+            LogCategory::System => RGB::from_u8(200, 200, 200),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub category : LogCategory,
+    pub text : String,
+    pub turn : u64,
+    /// Number of times this message was repeated or how many things it counts.
+    pub count : u32,
+    /// Set for counted messages, e.g. ("log.pick_up", "Gold").
+    counted : Option<(String, String)>,
+}
+
+impl LogEntry {
+    pub fn color (&self) -> RGB {
+        return self.category.color();
+    }
+
+    /// Text as displayed, including the repeat counter.
+    pub fn display_text (&self) -> String {
+        if self.counted.is_none() && self.count > 1 {
+            return format!("{} (x{})", self.text, self.count);
+        }
+        return self.text.clone();
+    }
+}
+
+/// Messages shown to the player, oldest first.
+#[derive(Default)]
+pub struct GameLog {
+    pub entries : Vec<LogEntry>,
+}
+
+impl GameLog {
+    /// Adds a message. A message identical to the last one on the same turn bumps its repeat counter instead.
+    pub fn push<S : ToString> (&mut self, category : LogCategory, text : S, turn : u64) {
+        let text = text.to_string();
+        match self.entries.last_mut() {
+            Some(last) if last.counted.is_none() && last.turn == turn && last.text == text => {
+                last.count += 1;
+                return;
+            },
+            _ => {},
+        }
+
+        self.entries.push(LogEntry {
+            category : category,
+            text : text,
+            turn : turn,
+            count : 1,
+            counted : Option::None,
+        });
+    }
+
+    /// Adds a message that counts things, such as "You pick up 3 Gold". Messages with the same
+    /// string key and noun on the same turn are merged into one; `text` words the message for a count.
+    pub fn push_counted<F : Fn(u32) -> String> (&mut self, category : LogCategory, key : &str, noun : &str, amount : u32, turn : u64, text : F) {
+        let key = (key.to_string(), noun.to_string());
+        match self.entries.last_mut() {
+            Some(last) if last.turn == turn && last.counted.as_ref() == Some(&key) => {
+                last.count += amount;
+                last.text = text(last.count);
+                return;
+            },
+            _ => {},
+        }
+
+        self.entries.push(LogEntry {
+            category : category,
+            text : text(amount),
+            turn : turn,
+            count : amount,
+            counted : Option::from(key),
+        });
+    }
+
+    /// Returns up to `amount` entries ending `offset` entries from the newest, oldest first.
+    pub fn recent (&self, amount : usize, offset : usize) -> &[LogEntry] {
+        let end = self.entries.len().saturating_sub(offset);
+        let start = end.saturating_sub(amount);
+        return &self.entries[start..end];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pick_up (count : u32, noun : &str) -> String {
+        return format!("You pick up {} {}.", count, noun);
+    }
+
+    #[test]
+    fn repeated_messages_are_counted () {
+        let mut log = GameLog::default();
+        log.push(LogCategory::Status, "You feel better.", 1);
+        log.push(LogCategory::Status, "You feel better.", 1);
+        log.push(LogCategory::Status, "You feel better.", 2);
+        assert_eq!(log.entries.len(), 2);
+        assert_eq!(log.entries[0].display_text(), "You feel better. (x2)");
+        assert_eq!(log.entries[1].display_text(), "You feel better.");
+    }
+
+    #[test]
+    fn counted_messages_merge_on_the_same_turn () {
+        let mut log = GameLog::default();
+        log.push_counted(LogCategory::Pickup, "log.pick_up", "Gold", 3, 1, |count| pick_up(count, "Gold"));
+        log.push_counted(LogCategory::Pickup, "log.pick_up", "Gold", 2, 1, |count| pick_up(count, "Gold"));
+        assert_eq!(log.entries.len(), 1);
+        assert_eq!(log.entries[0].count, 5);
+        assert_eq!(log.entries[0].display_text(), "You pick up 5 Gold.");
+
+        //a different noun, a later turn or a message in between each start a new entry
+        log.push_counted(LogCategory::Pickup, "log.pick_up", "Sword", 1, 1, |count| pick_up(count, "Sword"));
+        log.push_counted(LogCategory::Pickup, "log.pick_up", "Sword", 1, 2, |count| pick_up(count, "Sword"));
+        log.push(LogCategory::Status, "You feel better.", 2);
+        log.push_counted(LogCategory::Pickup, "log.pick_up", "Sword", 1, 2, |count| pick_up(count, "Sword"));
+        let texts : Vec<String> = log.entries.iter().map(|entry| entry.display_text()).collect();
+        assert_eq!(texts, vec!["You pick up 5 Gold.", "You pick up 1 Sword.", "You pick up 1 Sword.", "You feel better.", "You pick up 1 Sword."]);
+    }
+
+    #[test]
+    fn recent_counts_back_from_the_newest () {
+        let mut log = GameLog::default();
+        for turn in 0..5 {
+            log.push(LogCategory::System, format!("Turn {}", turn), turn);
+        }
+        let texts = |entries : &[LogEntry]| -> Vec<String> { entries.iter().map(|entry| entry.text.clone()).collect() };
+        assert_eq!(texts(log.recent(2, 0)), vec!["Turn 3", "Turn 4"]);
+        assert_eq!(texts(log.recent(2, 1)), vec!["Turn 2", "Turn 3"]);
+        assert_eq!(texts(log.recent(10, 4)), vec!["Turn 0"]);
+        assert!(log.recent(3, 9).is_empty());
+    }
+}
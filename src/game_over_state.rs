@@ -0,0 +1,111 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, ColorPair, RGB, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime, StateResult};
+use crate::raw::SharedRaws;
+use crate::locale::SharedStrings;
+
+/// Whether the player died or won.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ending {
+    /// The player died; holds what killed them.
+    Died(String),
+    Won,
+}
+
+/// How a game ended.
+#[derive(Debug, Clone)]
+pub struct GameSummary {
+    pub ending : Ending,
+    pub seed : String,
+    pub depth : u32,
+    pub gold : f32,
+    pub turns : u64,
+}
+
+/// Shows the summary of a finished game, then goes back to the main menu.
+pub struct GameOverState {
+    summary : GameSummary,
+    /// Handed back to the main menu, in case they were reloaded during the game.
+    raws : SharedRaws,
+    strings : SharedStrings,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl GameOverState {
+    pub fn new (summary : GameSummary, raws : SharedRaws, strings : SharedStrings) -> Self {
+        GameOverState {
+            summary : summary,
+            raws : raws,
+            strings : strings,
+            draw_batch : DrawBatch::new(),
+        }
+    }
+}
+
+impl State for GameOverState {
+
+    fn init (&mut self) {
+        match &self.summary.ending {
+            //the cause is in the player's language, so it stays out of the log
+            Ending::Died(_) => info!("Died after {} turns on depth {}", self.summary.turns, self.summary.depth),
+            Ending::Won => info!("Won after {} turns on depth {}", self.summary.turns, self.summary.depth),
+        }
+    }
+
+    fn on_enter (&mut self) {}
+
+    fn update (&mut self, _ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        match input.key {
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::Space) => {
+                return StateAction::PopWithResult(StateResult::Raws(self.raws.clone()), Option::from("Returning to the main menu.".to_string()));
+            },
+            _ => {},
+        }
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let screen_size = ctx.get_char_size();
+        let center = screen_size.1 as i32 / 2;
+        let text_color = ColorPair::new(RGB::from_u8(255, 255, 255), RGB::from_u8(0, 0, 0));
+        let (title, title_color) = match self.summary.ending {
+            Ending::Died(_) => ("game_over.title", RGB::from_u8(230, 60, 60)),
+            Ending::Won => ("game_over.victory", RGB::from_u8(238, 202, 0)),
+        };
+
+        self.draw_batch.target(0);
+        self.draw_batch.cls();
+        self.draw_batch.target(1);
+        self.draw_batch.cls();
+        self.draw_batch.print_color_centered(center - 5, self.strings.get(title), ColorPair::new(title_color, RGB::from_u8(0, 0, 0)));
+        match &self.summary.ending {
+            Ending::Died(cause) => {
+                self.draw_batch.print_color_centered(center - 3, cause, text_color);
+            },
+            Ending::Won => {},
+        }
+        self.draw_batch.print_color_centered(center - 1, self.strings.format("game_over.depth", &[&self.summary.depth]), text_color);
+        self.draw_batch.print_color_centered(center, self.strings.format("game_over.gold", &[&format!("{:.2}", self.summary.gold)]), text_color);
+        self.draw_batch.print_color_centered(center + 1, self.strings.format("game_over.turns", &[&self.summary.turns]), text_color);
+        self.draw_batch.print_color_centered(center + 2, self.strings.format("game_over.seed", &[&self.summary.seed]), text_color);
+        self.draw_batch.print_color_centered(center + 5, self.strings.get("game_over.help"), text_color);
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+}
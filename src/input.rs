@@ -0,0 +1,417 @@
+//! Translates raw key presses into the commands the game understands.
+
+use std::collections::{HashMap, BTreeMap};
+use bracket_lib::prelude::VirtualKeyCode;
+
+use crate::config::KeyBindingConfig;
+use crate::state::CurrentInput;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Command {
+    MoveNorth,
+    MoveSouth,
+    MoveEast,
+    MoveWest,
+    MoveNorthEast,
+    MoveNorthWest,
+    MoveSouthEast,
+    MoveSouthWest,
+    Wait,
+    PickUp,
+    OpenInventory,
+    OpenMessageLog,
+    CyclePanel,
+    ToggleLook,
+    AutoExplore,
+    ScrollLogUp,
+    ScrollLogDown,
+    Screenshot,
+    ReloadRaws,
+    CopySeed,
+    Back,
+}
+
+/// Every command, in the order they are listed and given priority when bindings conflict.
+pub const ALL_COMMANDS : [Command; 21] = [
+    Command::MoveNorth,
+    Command::MoveSouth,
+    Command::MoveEast,
+    Command::MoveWest,
+    Command::MoveNorthEast,
+    Command::MoveNorthWest,
+    Command::MoveSouthEast,
+    Command::MoveSouthWest,
+    Command::Wait,
+    Command::PickUp,
+    Command::OpenInventory,
+    Command::OpenMessageLog,
+    Command::CyclePanel,
+    Command::ToggleLook,
+    Command::AutoExplore,
+    Command::ScrollLogUp,
+    Command::ScrollLogDown,
+    Command::Screenshot,
+    Command::ReloadRaws,
+    Command::CopySeed,
+    Command::Back,
+];
+
+impl Command {
+    /// Name used for the command in the config file.
+    pub fn name (&self) -> &'static str {
+        match self {
+            Command::MoveNorth => "move_north",
+            Command::MoveSouth => "move_south",
+            Command::MoveEast => "move_east",
+            Command::MoveWest => "move_west",
+            Command::MoveNorthEast => "move_north_east",
+            Command::MoveNorthWest => "move_north_west",
+            Command::MoveSouthEast => "move_south_east",
+            Command::MoveSouthWest => "move_south_west",
+            Command::Wait => "wait",
+            Command::PickUp => "pick_up",
+            Command::OpenInventory => "open_inventory",
+            Command::OpenMessageLog => "open_message_log",
+            Command::CyclePanel => "cycle_panel",
+            Command::ToggleLook => "toggle_look",
+            Command::AutoExplore => "auto_explore",
+            Command::ScrollLogUp => "scroll_log_up",
+            Command::ScrollLogDown => "scroll_log_down",
+            Command::Screenshot => "screenshot",
+            Command::ReloadRaws => "reload_raws",
+            Command::CopySeed => "copy_seed",
+            Command::Back => "back",
+        }
+    }
+
+    pub fn from_name (name : &str) -> Option<Command> {
+        return ALL_COMMANDS.iter().find(|command| command.name() == name).copied();
+    }
+
+    /// The direction a movement command moves in.
+    pub fn direction (&self) -> Option<(i32, i32)> {
+        let delta = match self {
+            Command::MoveNorth => (0, -1),
+            Command::MoveSouth => (0, 1),
+            Command::MoveEast => (1, 0),
+            Command::MoveWest => (-1, 0),
+            Command::MoveNorthEast => (1, -1),
+            Command::MoveNorthWest => (-1, -1),
+            Command::MoveSouthEast => (1, 1),
+            Command::MoveSouthWest => (-1, 1),
+            _ => return Option::None,
+        };
+        return Option::from(delta);
+    }
+}
+
+/// A key together with the modifiers that have to be held with it.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct KeyChord {
+    pub key : VirtualKeyCode,
+    pub shift : bool,
+    pub ctrl : bool,
+    pub alt : bool,
+}
+
+impl KeyChord {
+    pub fn new (key : VirtualKeyCode) -> Self {
+        KeyChord {
+            key : key,
+            shift : false,
+            ctrl : false,
+            alt : false,
+        }
+    }
+
+    /// The chord pressed this frame, if any. Modifier keys on their own don't count, and neither do
+    /// keys outside `BINDABLE_KEYS`, since a chord using them couldn't be read back from the config.
+    pub fn from_input (input : &CurrentInput) -> Option<KeyChord> {
+        match input.key {
+            Some(key) if BINDABLE_KEYS.contains(&key) => {
+                return Option::from(KeyChord {
+                    key : key,
+                    shift : input.shift,
+                    ctrl : input.ctrl,
+                    alt : input.alt,
+                });
+            },
+            _ => return Option::None,
+        }
+    }
+
+    /// Parses chords such as "G", "shift+Period" or "ctrl+alt+F2".
+    pub fn parse (text : &str) -> Result<KeyChord, String> {
+        let parts : Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+        let (key_name, modifiers) = match parts.split_last() {
+            Some(t) => t,
+            None => return Err("Empty key binding".to_string()),
+        };
+        let mut chord = match parse_key(key_name) {
+            Some(key) => KeyChord::new(key),
+            None => return Err(format!("Unknown key \"{}\" in \"{}\"", key_name, text)),
+        };
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_str() {
+                "shift" => chord.shift = true,
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                _ => return Err(format!("Unknown modifier \"{}\" in \"{}\"", modifier, text)),
+            }
+        }
+        return Ok(chord);
+    }
+}
+
+impl std::fmt::Display for KeyChord {
+    fn fmt (&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "alt+")?;
+        }
+        if self.shift {
+            write!(f, "shift+")?;
+        }
+        return write!(f, "{:?}", self.key);
+    }
+}
+
+/// Keys that can be bound to commands.
+const BINDABLE_KEYS : [VirtualKeyCode; 90] = {
+    use VirtualKeyCode::*;
+    [
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+        Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+        Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down, Back, Return, Space, Tab,
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+        Add, Subtract, Multiply, Divide, Decimal, NumpadEnter,
+        Apostrophe, Backslash, Comma, Equals, Grave, LBracket, RBracket, Minus, Period, Semicolon, Slash,
+    ]
+};
+
+/// Looks up a key by the name winit gives it, e.g. "A", "Key1", "Numpad8" or "PageUp".
+pub fn parse_key (name : &str) -> Option<VirtualKeyCode> {
+    return BINDABLE_KEYS.iter().find(|key| format!("{:?}", key) == name).copied();
+}
+
+/// Keys every preset shares.
+fn common_bindings () -> Vec<(Command, Vec<&'static str>)> {
+    return vec![
+        (Command::Wait, vec!["Period"]),
+        (Command::PickUp, vec!["G", "Comma"]),
+        (Command::OpenInventory, vec!["I"]),
+        (Command::OpenMessageLog, vec!["M"]),
+        (Command::CyclePanel, vec!["Tab"]),
+        (Command::ToggleLook, vec!["L"]),
+        (Command::AutoExplore, vec!["O"]),
+        (Command::ScrollLogUp, vec!["PageUp"]),
+        (Command::ScrollLogDown, vec!["PageDown"]),
+        (Command::Screenshot, vec!["F2"]),
+        (Command::ReloadRaws, vec!["F5"]),
+        (Command::CopySeed, vec!["F3"]),
+        (Command::Back, vec!["Escape"]),
+    ];
+}
+
+/// Names of the built in presets.
+pub const PRESETS : [&str; 3] = ["default", "vi", "numpad"];
+
+/// Bindings of a preset, by command.
+pub fn preset_bindings (preset : &str) -> Vec<(Command, Vec<&'static str>)> {
+    let mut bindings = match preset {
+        "vi" => vec![
+            (Command::MoveNorth, vec!["K", "Up"]),
+            (Command::MoveSouth, vec!["J", "Down"]),
+            (Command::MoveEast, vec!["L", "Right"]),
+            (Command::MoveWest, vec!["H", "Left"]),
+            (Command::MoveNorthEast, vec!["U"]),
+            (Command::MoveNorthWest, vec!["Y"]),
+            (Command::MoveSouthEast, vec!["N"]),
+            (Command::MoveSouthWest, vec!["B"]),
+        ],
+        "numpad" => vec![
+            (Command::MoveNorth, vec!["Numpad8"]),
+            (Command::MoveSouth, vec!["Numpad2"]),
+            (Command::MoveEast, vec!["Numpad6"]),
+            (Command::MoveWest, vec!["Numpad4"]),
+            (Command::MoveNorthEast, vec!["Numpad9"]),
+            (Command::MoveNorthWest, vec!["Numpad7"]),
+            (Command::MoveSouthEast, vec!["Numpad3"]),
+            (Command::MoveSouthWest, vec!["Numpad1"]),
+        ],
+        _ => vec![
+            (Command::MoveNorth, vec!["Up", "Numpad8"]),
+            (Command::MoveSouth, vec!["Down", "Numpad2"]),
+            (Command::MoveEast, vec!["Right", "Numpad6"]),
+            (Command::MoveWest, vec!["Left", "Numpad4"]),
+            (Command::MoveNorthEast, vec!["Numpad9"]),
+            (Command::MoveNorthWest, vec!["Numpad7"]),
+            (Command::MoveSouthEast, vec!["Numpad3"]),
+            (Command::MoveSouthWest, vec!["Numpad1"]),
+        ],
+    };
+    bindings.extend(common_bindings());
+    match preset {
+        // l moves east with vi-keys
+        "vi" => {
+            for binding in bindings.iter_mut() {
+                if binding.0 == Command::ToggleLook {
+                    binding.1 = vec!["X"];
+                }
+            }
+        },
+        "numpad" => {
+            for binding in bindings.iter_mut() {
+                if binding.0 == Command::Wait {
+                    binding.1 = vec!["Numpad5"];
+                }
+            }
+        },
+        _ => {},
+    }
+    return bindings;
+}
+
+/// Maps key chords to commands.
+#[derive(Default)]
+pub struct KeyBindings {
+    commands : HashMap<KeyChord, Command>,
+    /// Chords bound to each command, for display.
+    chords : BTreeMap<&'static str, Vec<KeyChord>>,
+}
+
+impl KeyBindings {
+    /// Builds the bindings from a preset and the overrides in the config. Returns the bindings and a
+    /// description of every problem found, such as unknown keys or a chord bound to two commands.
+    /// When two commands share a chord, overridden commands win over preset ones, then earlier
+    /// commands in `ALL_COMMANDS` win over later ones.
+    pub fn from_config (config : &KeyBindingConfig) -> (KeyBindings, Vec<String>) {
+        let mut problems = Vec::new();
+        if !PRESETS.contains(&config.preset.as_str()) {
+            problems.push(format!("Unknown key binding preset \"{}\"; using \"default\"", config.preset));
+        }
+
+        let mut by_command : Vec<(Command, Vec<String>, bool)> = preset_bindings(&config.preset).into_iter()
+            .map(|(command, chords)| (command, chords.iter().map(|chord| chord.to_string()).collect(), false))
+            .collect();
+        for (name, chords) in config.bindings.iter() {
+            match Command::from_name(name) {
+                Some(command) => {
+                    match by_command.iter_mut().find(|binding| binding.0 == command) {
+                        Some(binding) => {
+                            binding.1 = chords.clone();
+                            binding.2 = true;
+                        },
+                        None => by_command.push((command, chords.clone(), true)),
+                    }
+                },
+                None => problems.push(format!("Unknown command \"{}\" in key bindings", name)),
+            }
+        }
+        by_command.sort_by_key(|binding| (!binding.2, ALL_COMMANDS.iter().position(|command| *command == binding.0)));
+
+        let mut bindings = KeyBindings::default();
+        for (command, chords, _overridden) in by_command {
+            for text in chords.iter() {
+                let chord = match KeyChord::parse(text) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        problems.push(e);
+                        continue;
+                    },
+                };
+                match bindings.commands.get(&chord) {
+                    Some(existing) if *existing != command => {
+                        problems.push(format!("\"{}\" is bound to both {} and {}; keeping {}", chord, existing.name(), command.name(), existing.name()));
+                    },
+                    Some(_existing) => {},
+                    None => {
+                        bindings.commands.insert(chord, command);
+                        bindings.chords.entry(command.name()).or_default().push(chord);
+                    },
+                }
+            }
+        }
+        return (bindings, problems);
+    }
+
+    /// The command the input triggers, if any.
+    pub fn command (&self, input : &CurrentInput) -> Option<Command> {
+        return KeyChord::from_input(input).and_then(|chord| self.commands.get(&chord).copied());
+    }
+
+    /// Chords bound to a command, e.g. "Up, Numpad8", or `None` if nothing is bound to it.
+    pub fn describe (&self, command : Command) -> Option<String> {
+        return self.chords.get(command.name()).map(|chords| chords.iter().map(|chord| chord.to_string()).collect::<Vec<String>>().join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press (key : VirtualKeyCode) -> CurrentInput {
+        return CurrentInput {
+            key : Option::from(key),
+            ..Default::default()
+        };
+    }
+
+    fn config (preset : &str, bindings : &[(&str, &[&str])]) -> KeyBindingConfig {
+        return KeyBindingConfig {
+            preset : preset.to_string(),
+            bindings : bindings.iter().map(|(name, chords)| (name.to_string(), chords.iter().map(|chord| chord.to_string()).collect())).collect(),
+        };
+    }
+
+    #[test]
+    fn vi_preset_moves_look_to_x () {
+        let (bindings, problems) = KeyBindings::from_config(&config("vi", &[]));
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(bindings.command(&press(VirtualKeyCode::L)), Option::from(Command::MoveEast));
+        assert_eq!(bindings.command(&press(VirtualKeyCode::X)), Option::from(Command::ToggleLook));
+        assert_eq!(bindings.describe(Command::ToggleLook), Option::from("X".to_string()));
+    }
+
+    #[test]
+    fn overrides_beat_the_preset () {
+        let (bindings, problems) = KeyBindings::from_config(&config("vi", &[("toggle_look", &["L"])]));
+        assert_eq!(bindings.command(&press(VirtualKeyCode::L)), Option::from(Command::ToggleLook));
+        assert_eq!(bindings.command(&press(VirtualKeyCode::X)), Option::None);
+        assert_eq!(problems, vec!["\"L\" is bound to both toggle_look and move_east; keeping toggle_look".to_string()]);
+    }
+
+    #[test]
+    fn chords_parse_with_modifiers () {
+        let chord = KeyChord::parse("ctrl+alt+F2").unwrap();
+        assert_eq!(chord, KeyChord { key : VirtualKeyCode::F2, shift : false, ctrl : true, alt : true });
+        assert_eq!(chord.to_string(), "ctrl+alt+F2");
+        assert_eq!(KeyChord::parse(" Shift + Period ").unwrap(), KeyChord { key : VirtualKeyCode::Period, shift : true, ctrl : false, alt : false });
+
+        assert_eq!(KeyChord::parse("super+F2"), Err("Unknown modifier \"super\" in \"super+F2\"".to_string()));
+        assert_eq!(KeyChord::parse("ctrl+Banana"), Err("Unknown key \"Banana\" in \"ctrl+Banana\"".to_string()));
+    }
+
+    #[test]
+    fn captured_chords_read_back_from_the_config () {
+        for key in BINDABLE_KEYS.iter() {
+            for modifiers in 0..8 {
+                let input = CurrentInput {
+                    key : Option::from(*key),
+                    shift : modifiers & 1 != 0,
+                    ctrl : modifiers & 2 != 0,
+                    alt : modifiers & 4 != 0,
+                    ..Default::default()
+                };
+                let chord = KeyChord::from_input(&input).unwrap();
+                assert_eq!(KeyChord::parse(&chord.to_string()), Ok(chord));
+            }
+        }
+
+        assert_eq!(KeyChord::from_input(&press(VirtualKeyCode::LShift)), Option::None);
+        assert_eq!(KeyChord::from_input(&press(VirtualKeyCode::Mute)), Option::None);
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, Rect, RGB, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime, WorldAction, StateResult};
+use crate::components::gui::{Panel, Justification};
+use crate::raw::{RawMaster, ItemRaw, ItemType};
+use crate::locale::{SharedStrings, Strings};
+use crate::widgets::{Label, ListView, Tabs, Modal, ModalResult, draw_window, FRAME_FG};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SortOrder {
+    Name,
+    Value,
+    Weight,
+}
+
+impl SortOrder {
+    fn next (&self) -> SortOrder {
+        match self {
+            SortOrder::Name => SortOrder::Value,
+            SortOrder::Value => SortOrder::Weight,
+            SortOrder::Weight => SortOrder::Name,
+        }
+    }
+
+    /// Key of the order's name in the string table.
+    fn key (&self) -> &'static str {
+        match self {
+            SortOrder::Name => "inventory.sort.name",
+            SortOrder::Value => "inventory.sort.value",
+            SortOrder::Weight => "inventory.sort.weight",
+        }
+    }
+}
+
+/// What the open dialog is for.
+enum Dialog {
+    /// Picking what to do with the selected item.
+    Actions(Vec<ItemAction>),
+    /// Showing the selected item's description.
+    Inspect,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum ItemAction {
+    Use,
+    Equip,
+    Drop,
+    Inspect,
+}
+
+impl ItemAction {
+    /// Key of the action's name in the string table.
+    fn key (&self) -> &'static str {
+        match self {
+            ItemAction::Use => "inventory.action.use",
+            ItemAction::Equip => "inventory.action.equip",
+            ItemAction::Drop => "inventory.action.drop",
+            ItemAction::Inspect => "inventory.action.inspect",
+        }
+    }
+}
+
+struct InventoryEntry {
+    id : u32,
+    amount : u32,
+    raw : ItemRaw,
+    /// Name shown to the player.
+    name : String,
+}
+
+impl InventoryEntry {
+    fn can_use (&self) -> bool {
+        return self.raw.potion.is_some() || self.raw.consumable.is_some();
+    }
+
+    fn can_equip (&self) -> bool {
+        return self.raw.melee_weapon.is_some() || self.raw.equipable.is_some();
+    }
+
+    /// Lines describing the item, shown in the tooltip and when inspecting.
+    fn description (&self, strings : &Strings) -> Vec<String> {
+        let mut lines = vec![
+            self.name.clone(),
+            strings.format("inventory.type", &[&strings.item_type_name(self.raw.item_type)]),
+            strings.format("inventory.weight", &[&format!("{:.2}", self.raw.weight)]),
+            strings.format("inventory.value", &[&format!("{:.2}", self.raw.value)]),
+        ];
+        match &self.raw.melee_weapon {
+            Some(weapon) => {
+                lines.push(strings.format("inventory.damage", &[&weapon.base_damage]));
+                match &weapon.effects {
+                    Some(effects) => lines.push(strings.format("inventory.on_hit", &[&effects.join(", ")])),
+                    None => {},
+                }
+            },
+            None => {},
+        }
+        match &self.raw.potion {
+            Some(potion) => {
+                match &potion.effects {
+                    Some(effects) => lines.push(strings.format("inventory.effects", &[&effects.join(", ")])),
+                    None => {},
+                }
+            },
+            None => {},
+        }
+        match &self.raw.consumable {
+            Some(consumable) => {
+                match &consumable.effects {
+                    Some(effects) => lines.push(strings.format("inventory.effects", &[&effects.join(", ")])),
+                    None => {},
+                }
+            },
+            None => {},
+        }
+        match &self.raw.equipable {
+            Some(equipable) => lines.push(strings.format("inventory.slot", &[&strings.equip_slot_name(equipable.slot)])),
+            None => {},
+        }
+        match &self.raw.light {
+            Some(light) => lines.push(strings.format("inventory.light_radius", &[&light.radius])),
+            None => {},
+        }
+        return lines;
+    }
+}
+
+/// Lists the player's items and lets the player use, equip, drop or inspect them.
+pub struct InventoryState {
+    entries : Vec<InventoryEntry>,
+    money : f32,
+    equipped : Option<u32>,
+    sort : SortOrder,
+    tabs : Tabs,
+    /// Item type listed under each tab after "All".
+    tab_types : Vec<ItemType>,
+    list : ListView,
+    /// Indices into `entries` of the items listed under the active tab, in list order.
+    shown : Vec<usize>,
+    dialog : Option<(Dialog, Modal)>,
+    status : String,
+    strings : SharedStrings,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl InventoryState {
+    pub fn new (raws : &RawMaster, strings : SharedStrings, items : &HashMap<u32, u32>, money : f32, equipped : Option<u32>) -> Self {
+        let mut entries = Vec::new();
+        for (id, amount) in items.iter() {
+            match raws.get_item(*id) {
+                Some(raw) => {
+                    entries.push(InventoryEntry {
+                        id : *id,
+                        amount : *amount,
+                        raw : raw.clone(),
+                        name : strings.item_name(raw),
+                    });
+                },
+                None => error!("No item found with the id {}", id),
+            }
+        }
+
+        //one tab per item type
+        let mut tab_types : Vec<ItemType> = Vec::new();
+        for entry in entries.iter() {
+            if !tab_types.contains(&entry.raw.item_type) {
+                tab_types.push(entry.raw.item_type);
+            }
+        }
+        tab_types.sort();
+        let mut titles = vec![strings.get("inventory.all").to_string()];
+        titles.extend(tab_types.iter().map(|item_type| strings.item_type_name(*item_type)));
+
+        let mut state = InventoryState {
+            entries : entries,
+            money : money,
+            equipped : equipped,
+            sort : SortOrder::Name,
+            tabs : Tabs::new(titles),
+            tab_types : tab_types,
+            list : ListView::new(Vec::new()),
+            shown : Vec::new(),
+            dialog : Option::None,
+            status : String::new(),
+            strings : strings,
+            draw_batch : DrawBatch::new(),
+        };
+        state.refresh_list();
+        return state;
+    }
+
+    /// Rebuilds the list for the active tab, grouped by item type and sorted within each group.
+    fn refresh_list (&mut self) {
+        let group = if self.tabs.active == 0 { Option::None } else { self.tab_types.get(self.tabs.active - 1).copied() };
+        let mut shown : Vec<usize> = (0..self.entries.len())
+            .filter(|idx| group.is_none() || group == Option::from(self.entries[*idx].raw.item_type))
+            .collect();
+
+        let entries = &self.entries;
+        let sort = self.sort;
+        shown.sort_by(|a, b| {
+            let (a_name, b_name) = (&entries[*a].name, &entries[*b].name);
+            let a = &entries[*a].raw;
+            let b = &entries[*b].raw;
+            let by_group = a.item_type.cmp(&b.item_type);
+            let within_group = match sort {
+                SortOrder::Name => a_name.cmp(b_name),
+                SortOrder::Value => b.value.partial_cmp(&a.value).unwrap_or(std::cmp::Ordering::Equal),
+                SortOrder::Weight => b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal),
+            };
+            by_group.then(within_group).then(a_name.cmp(b_name))
+        });
+
+        let items = shown.iter().map(|idx| {
+            let entry = &self.entries[*idx];
+            let equipped = if self.equipped == Option::from(entry.id) { self.strings.get("inventory.equipped") } else { "" };
+            let item_type = self.strings.item_type_name(entry.raw.item_type);
+            format!("{:<12} {} x{}{}", item_type, entry.name, entry.amount, equipped)
+        }).collect();
+
+        let selected = self.list.selected;
+        self.list = ListView::new(items);
+        self.list.selected = selected.min(shown.len().saturating_sub(1));
+        self.shown = shown;
+    }
+
+    fn selected_entry (&self) -> Option<&InventoryEntry> {
+        return self.shown.get(self.list.selected).map(|idx| &self.entries[*idx]);
+    }
+
+    /// The window the inventory is drawn in, over the game.
+    fn screen (ctx : &BTerm) -> Panel {
+        let screen_size = ctx.get_char_size();
+        return Panel::new(Rect::with_size(4, 2, screen_size.0 as i32 - 9, screen_size.1 as i32 - 4), true, Justification::CENTER, Option::None);
+    }
+
+    /// Lays out the screen as tabs over the list on the left and the tooltip on the right.
+    fn layout (ctx : &BTerm) -> (Panel, Panel, Panel) {
+        let screen = InventoryState::screen(ctx);
+        let content = screen.content();
+        let list_width = content.width() * 3 / 5;
+        let tab_area = screen.child(Rect::with_size(0, 1, list_width, content.height() - 3));
+        let tooltip = screen.child(Rect::with_size(list_width + 1, 1, content.width() - list_width - 1, content.height() - 3));
+        return (screen, tab_area, tooltip);
+    }
+
+    /// Carries out an action on the selected item, either by handing it to the game or by opening a dialog.
+    fn act (&mut self, action : ItemAction) -> StateAction {
+        let (id, key, name, can_use, can_equip, description) = match self.selected_entry() {
+            Some(entry) => (entry.id, entry.raw.id.clone(), entry.name.clone(), entry.can_use(), entry.can_equip(), entry.description(&self.strings)),
+            None => return StateAction::NoAction,
+        };
+        match action {
+            ItemAction::Use => {
+                if can_use {
+                    return StateAction::PopWithResult(StateResult::World(WorldAction::PlayerUseItem(id)), Option::from(format!("Using {}", key)));
+                }
+                self.status = self.strings.format("inventory.cant_use", &[&name]);
+            },
+            ItemAction::Equip => {
+                if can_equip {
+                    return StateAction::PopWithResult(StateResult::World(WorldAction::PlayerEquipItem(id)), Option::from(format!("Equipping {}", key)));
+                }
+                self.status = self.strings.format("inventory.cant_equip", &[&name]);
+            },
+            ItemAction::Drop => {
+                return StateAction::PopWithResult(StateResult::World(WorldAction::PlayerDropItem(id)), Option::from(format!("Dropping {}", key)));
+            },
+            ItemAction::Inspect => {
+                self.dialog = Option::from((Dialog::Inspect, Modal::new(name, description, vec![self.strings.get("inventory.close").to_string()])));
+            },
+        }
+        return StateAction::NoAction;
+    }
+
+    fn open_actions (&mut self) {
+        let (name, can_use, can_equip) = match self.selected_entry() {
+            Some(entry) => (entry.name.clone(), entry.can_use(), entry.can_equip()),
+            None => return,
+        };
+        let mut actions = Vec::new();
+        if can_use {
+            actions.push(ItemAction::Use);
+        }
+        if can_equip {
+            actions.push(ItemAction::Equip);
+        }
+        actions.push(ItemAction::Drop);
+        actions.push(ItemAction::Inspect);
+        let options = actions.iter().map(|action| self.strings.get(action.key()).to_string()).collect();
+        self.dialog = Option::from((Dialog::Actions(actions), Modal::new(name, Vec::new(), options)));
+    }
+}
+
+impl State for InventoryState {
+
+    fn init (&mut self) {}
+
+    fn on_enter (&mut self) {}
+
+    fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        let (screen, tab_area, _tooltip) = InventoryState::layout(ctx);
+
+        //an open dialog takes all input
+        match self.dialog.take() {
+            Some((dialog, mut modal)) => {
+                match modal.update(&input, screen.bounds) {
+                    ModalResult::Pending => self.dialog = Option::from((dialog, modal)),
+                    ModalResult::Cancelled => {},
+                    ModalResult::Chosen(idx) => {
+                        match dialog {
+                            Dialog::Actions(actions) => return self.act(actions[idx]),
+                            Dialog::Inspect => {},
+                        }
+                    },
+                }
+                return StateAction::NoAction;
+            },
+            None => {},
+        }
+
+        match input.key {
+            Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::I) => {
+                return StateAction::Pop(Option::from("Closed inventory.".to_string()));
+            },
+            Some(VirtualKeyCode::S) => {
+                self.sort = self.sort.next();
+                self.refresh_list();
+                return StateAction::NoAction;
+            },
+            Some(VirtualKeyCode::U) => return self.act(ItemAction::Use),
+            Some(VirtualKeyCode::E) => return self.act(ItemAction::Equip),
+            Some(VirtualKeyCode::D) => return self.act(ItemAction::Drop),
+            Some(VirtualKeyCode::X) => return self.act(ItemAction::Inspect),
+            _ => {},
+        }
+
+        if self.tabs.update(&input, &tab_area) {
+            self.list.selected = 0;
+            self.refresh_list();
+        }
+        let list_area = self.tabs.content(&tab_area);
+        match self.list.update(&input, &list_area) {
+            Some(_idx) => self.open_actions(),
+            None => {},
+        }
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let (screen, tab_area, tooltip) = InventoryState::layout(ctx);
+
+        self.draw_batch.target(0);
+        draw_window(&mut self.draw_batch, &screen, self.strings.get("inventory.title"));
+
+        let header = self.strings.format("inventory.header", &[&format!("{:.2}", self.money), &self.strings.get(self.sort.key())]);
+        Label::new(header).draw(&mut self.draw_batch, &screen.row(0));
+        self.tabs.draw(&mut self.draw_batch, &tab_area);
+        let list_area = self.tabs.content(&tab_area);
+        if self.list.items.is_empty() {
+            Label::new(self.strings.get("inventory.empty")).draw(&mut self.draw_batch, &list_area.row(0));
+        } else {
+            self.list.draw(&mut self.draw_batch, &list_area);
+        }
+
+        //tooltip for the selected item
+        let description = match self.selected_entry() {
+            Some(entry) => entry.description(&self.strings),
+            None => Vec::new(),
+        };
+        for (row, line) in description.iter().enumerate() {
+            Label::new(line).with_color(if row == 0 { RGB::from_u8(238, 202, 0) } else { FRAME_FG }).draw(&mut self.draw_batch, &tooltip.row(row as i32));
+        }
+
+        let bottom = screen.content().height() - 1;
+        let help = if self.status.is_empty() { self.strings.get("inventory.help").to_string() } else { self.status.clone() };
+        Label::new(help).draw(&mut self.draw_batch, &screen.row(bottom));
+
+        match &self.dialog {
+            Some((_dialog, modal)) => modal.draw(&mut self.draw_batch, screen.bounds),
+            None => {},
+        }
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+
+    fn is_transparent (&self) -> bool {
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::locale::Strings;
+
+    fn inventory (ids : &[&str]) -> InventoryState {
+        let mut raws = RawMaster::empty();
+        raws.load_raws().unwrap();
+        let mut items = HashMap::new();
+        for id in ids.iter() {
+            items.insert(raws.get_item_id(id).unwrap(), 1);
+        }
+        return InventoryState::new(&raws, Arc::new(Strings::load("en")), &items, 0.0, Option::None);
+    }
+
+    fn shown_names (state : &InventoryState) -> Vec<&str> {
+        return state.shown.iter().map(|idx| state.entries[*idx].name.as_str()).collect();
+    }
+
+    #[test]
+    fn items_are_grouped_by_type_then_sorted () {
+        let mut state = inventory(&["lantern", "silver", "diamond", "murky_potion", "copper", "health_potion", "flickering_gem"]);
+        assert_eq!(shown_names(&state), vec!["Health Potion", "Murky Potion", "Copper", "Silver", "Diamond", "Flickering Gem", "Lantern"]);
+
+        state.sort = SortOrder::Value;
+        state.refresh_list();
+        assert_eq!(shown_names(&state), vec!["Health Potion", "Murky Potion", "Silver", "Copper", "Diamond", "Flickering Gem", "Lantern"]);
+
+        //equal weights fall back on the name
+        state.sort = SortOrder::Weight;
+        state.refresh_list();
+        assert_eq!(shown_names(&state), vec!["Health Potion", "Murky Potion", "Copper", "Silver", "Diamond", "Flickering Gem", "Lantern"]);
+    }
+
+    #[test]
+    fn tabs_show_one_item_type () {
+        let mut state = inventory(&["lantern", "silver", "diamond", "health_potion", "flickering_gem"]);
+        assert_eq!(state.tab_types, vec![ItemType::Potion, ItemType::Currency, ItemType::Treasure, ItemType::LightSource]);
+
+        state.tabs.active = 3;
+        state.sort = SortOrder::Value;
+        state.refresh_list();
+        assert_eq!(shown_names(&state), vec!["Diamond", "Flickering Gem"]);
+    }
+}
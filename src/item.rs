@@ -45,91 +45,245 @@
 //* Deaf (hearing based abilities dont work and no music/sound)
 //* Fatigued (stamina drain)
 
-use specs::{Entity, EntityBuilder, Builder};
-use crate::components::basic::{Renderable, Position, ItemWrapper, Currency};
+use specs::{Entity, EntityBuilder, Builder, World, WorldExt, Component, WriteStorage};
+use rand::Rng;
+use crate::components::basic::{Renderable, Position, ItemWrapper, Currency, Light, ColorLerp, CycleAnimation, Equipable, Consumable};
 use bracket_lib::prelude::RGB;
 use crate::raw::*;
-use crate::systems::render::ObjectShader;
+use crate::systems::render::{ObjectShader, RenderLayer};
+use crate::tileset::SpriteMap;
 
 pub struct ItemBuilder;
 
 impl ItemBuilder {
-    pub fn build_item_with_name (entity : EntityBuilder, name : String, position : (i32, i32)) -> Entity {
-        let id = RAW.lock().unwrap().get_item_id(name);
-        let item = RAW.lock().unwrap().get_item(id).clone();
-        return ItemBuilder::build_item(entity, item, position);
+    /// Builds the item with the given id. Returns `None`, and builds nothing, if there is no such item.
+    pub fn build_item_with_id (entity : EntityBuilder, id : u32, position : (i32, i32)) -> Option<Entity> {
+        let item = entity.world.fetch::<SharedRaws>().get_item(id).cloned();
+        match item {
+            Some(item) => return Option::from(ItemBuilder::build_item(entity, item, position)),
+            None => {
+                error!("No item found with the id {}", id);
+                return Option::None;
+            },
+        }
     }
 
-    pub fn build_item_with_id (entity : EntityBuilder, id : u32, position : (i32, i32)) -> Entity {
-        let item = RAW.lock().unwrap().get_item(id).clone();
-        return ItemBuilder::build_item(entity, item, position);
+    fn build_item (entity : EntityBuilder, item : ItemRaw, position : (i32, i32)) -> Entity {
+        let world = entity.world;
+        let item_entity = entity
+            .with(Position::new(position.0, position.1))
+            .with(ItemWrapper::new(item.clone()))
+            .build();
+        ItemBuilder::compose(world, item_entity, &item);
+        return item_entity;
     }
 
-    fn build_item (entity : EntityBuilder, item : ItemRaw, position : (i32, i32)) -> Entity {
-        let mut item_entity = entity;
-        item_entity = item_entity.with(Position::new(position.0, position.1));
-        match item.item_type.as_str() {
-            "potion" => {
-                item_entity = item_entity.with(Renderable::new(235, RGB::from_u8(0, 0, 255), RGB::from_u8(0, 0, 0), ObjectShader::Foreground, ObjectShader::Background));
-            },
-            "currency" => {
-                item_entity = item_entity.with(Currency{amt : 1});
-                item_entity = ItemBuilder::build_renderable(item_entity, item.clone());
-            },
-            _ => {
-                item_entity = ItemBuilder::build_renderable(item_entity, item.clone());
-            },
+    /// Gives an item the components its raw declares, and takes away any it no longer declares.
+    /// A currency pile keeps the amount it already has.
+    fn compose (world : &World, entity : Entity, item : &ItemRaw) {
+        let renderable = ItemBuilder::item_renderable(&world.fetch::<SpriteMap>(), item);
+        set_component(&mut world.write_storage::<Renderable>(), entity, Option::from(renderable), &item.id);
+
+        let light = item.light.as_ref().map(|light| Light::new(light.radius, light.intensity, item_color(&light.color, &item.id)));
+        set_component(&mut world.write_storage::<Light>(), entity, light, &item.id);
+
+        let color_lerp = item.color_lerp.as_ref().map(|lerp| {
+            ColorLerp::new(item_color(&lerp.color_a, &item.id), item_color(&lerp.color_b, &item.id), lerp.rate, lerp.offset)
+        });
+        set_component(&mut world.write_storage::<ColorLerp>(), entity, color_lerp, &item.id);
+
+        //frames outside the font are dropped rather than drawn as garbage
+        let cycle_animation = item.cycle_animation.as_ref().and_then(|animation| {
+            let frames : Vec<u16> = animation.frames.iter().filter(|frame| **frame >= 0 && **frame <= 255).map(|frame| *frame as u16).collect();
+            if frames.is_empty() { Option::None } else { Option::from(CycleAnimation::new(animation.rate, frames)) }
+        });
+        set_component(&mut world.write_storage::<CycleAnimation>(), entity, cycle_animation, &item.id);
+
+        let mut currencies = world.write_storage::<Currency>();
+        if item.item_type == ItemType::Currency || item.currency.is_some() {
+            if !currencies.contains(entity) {
+                let amt = match &item.currency {
+                    Some(currency) if currency.min < currency.max => rand::thread_rng().gen_range(currency.min, currency.max + 1),
+                    Some(currency) => currency.min,
+                    None => 1,
+                };
+                set_component(&mut currencies, entity, Option::from(Currency{amt : amt}), &item.id);
+            }
+        } else {
+            set_component(&mut currencies, entity, Option::None, &item.id);
         }
 
-        item_entity = item_entity.with(ItemWrapper::new(item.clone()));
-        return item_entity.build();
+        let equipable = item.equipable.as_ref().map(|equipable| Equipable { slot : equipable.slot });
+        set_component(&mut world.write_storage::<Equipable>(), entity, equipable, &item.id);
+
+        let consumable = item.consumable.as_ref().map(|consumable| Consumable { effects : consumable.effects.clone().unwrap_or_default() });
+        set_component(&mut world.write_storage::<Consumable>(), entity, consumable, &item.id);
     }
 
-    fn build_renderable (builder : EntityBuilder, raw_item : ItemRaw) -> EntityBuilder {
-        let mut temp_builder = builder;
-        match raw_item.renderable {
+    /// Returns the renderable for an item, falling back on defaults for any bad values.
+    pub fn item_renderable (sprites : &SpriteMap, raw_item : &ItemRaw) -> Renderable {
+        match &raw_item.renderable {
             Some(r) => {
                 //handle error for invalid foreground color:
-                let foreground_result = RGB::from_hex(r.fg);
+                let foreground_result = RGB::from_hex(&r.fg);
                 let mut fg = RGB::from_u8(255, 0, 0);
                 match foreground_result {
                     Ok(t) => {
                         fg = t;
                     },
                     Err(_e) => {
-                        error!("Invalid RGB foreground for item \"{}\"; falling back on default color", raw_item.name);
+                        error!("Invalid RGB foreground for item \"{}\"; falling back on default color", raw_item.id);
                     },
                 }
                 //handle error for invalid background color:
-                let background_result = RGB::from_hex(r.bg);
+                let background_result = RGB::from_hex(&r.bg);
                 let mut bg = RGB::from_u8(0, 0, 0);
                 match background_result {
                     Ok(t) => {
                         bg = t;
                     },
                     Err(_e) => {
-                        error!("Invalid RGB background for item \"{}\"; falling back on default color", raw_item.name);
+                        error!("Invalid RGB background for item \"{}\"; falling back on default color", raw_item.id);
                     },
                 }
                 //handle error for invalid glyph:
                 let glyph_raw = r.character_code;
                 let mut glyph = '!' as u16;
                 if glyph_raw > 255 {
-                    error!("Invalid character code for renderable for item \"{}\"; Falling back on default", raw_item.name);
+                    error!("Invalid character code for renderable for item \"{}\"; Falling back on default", raw_item.id);
                 } else {
                     glyph = glyph_raw as u16;
                 }
                 
-                temp_builder = temp_builder.with(Renderable::new(glyph, fg, bg, ObjectShader::Foreground, ObjectShader::Background));
+                //a named sprite takes priority over the character code
+                let renderable = match &r.sprite {
+                    Some(sprite) => Renderable::new_from_sprite(sprites, sprite, fg, bg, ObjectShader::Foreground, ObjectShader::Background),
+                    None => Renderable::new(glyph, fg, bg, ObjectShader::Foreground, ObjectShader::Background),
+                };
+                return renderable.with_layer(RenderLayer::Items);
             },
             _ => {
-                temp_builder = temp_builder.with(Renderable::new_from_char('!', RGB::from_u8(255, 0, 0), RGB::from_u8(0, 0, 0), ObjectShader::Foreground, ObjectShader::Background));
-                error!("Item: \"{}\" missing renderable component; Failing back on defaults", raw_item.name);
+                error!("Item: \"{}\" missing renderable component; Failing back on defaults", raw_item.id);
+                return Renderable::new_from_char('!', RGB::from_u8(255, 0, 0), RGB::from_u8(0, 0, 0), ObjectShader::Foreground, ObjectShader::Background).with_layer(RenderLayer::Items);
+            }
+        }
+    }
+
+    /// Gives every item in the world the data of its kind from the world's raws, and rebuilds its
+    /// components from it. Returns the ids of items that are no longer in the raws; these are
+    /// left unchanged.
+    pub fn refresh_items (world : &World) -> Vec<String> {
+        use specs::Join;
+        let mut refreshed = Vec::new();
+        let mut missing = Vec::new();
+        {
+        let raws = world.fetch::<SharedRaws>();
+        let mut item_wrappers = world.write_storage::<ItemWrapper>();
+        for (entity, item_wrapper) in (&world.entities(), &mut item_wrappers).join() {
+            match raws.get_item_id(&item_wrapper.item_data.id).and_then(|id| raws.get_item(id)) {
+                Some(item) => {
+                    item_wrapper.item_data = item.clone();
+                    refreshed.push((entity, item.clone()));
+                },
+                None => {
+                    if !missing.contains(&item_wrapper.item_data.id) {
+                        missing.push(item_wrapper.item_data.id.clone());
+                    }
+                },
             }
         }
-        return temp_builder;
+        }
+
+        for (entity, item) in refreshed.iter() {
+            ItemBuilder::compose(world, *entity, item);
+        }
+        return missing;
+    }
+}
+
+/// Adds the component to the entity, or removes the entity's component of that type if there is none.
+fn set_component<T : Component> (storage : &mut WriteStorage<T>, entity : Entity, component : Option<T>, item_key : &str) {
+    match component {
+        Some(component) => {
+            match storage.insert(entity, component) {
+                Ok(_v) => {},
+                Err(e) => error!("Unable to add a component to \"{}\" : {}", item_key, e),
+            }
+        },
+        None => {
+            storage.remove(entity);
+        },
+    }
+}
+
+/// Parses a color from the raws, falling back on white if it is invalid.
+fn item_color (hex : &str, item_key : &str) -> RGB {
+    match RGB::from_hex(hex) {
+        Ok(color) => return color,
+        Err(_e) => {
+            error!("Invalid color \"{}\" for item \"{}\"; falling back on white", hex, item_key);
+            return RGB::from_u8(255, 255, 255);
+        },
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
 
+    #[test]
+    fn refreshed_items_gain_and_lose_components () {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<ItemWrapper>();
+        world.register::<Renderable>();
+        world.register::<Light>();
+        world.register::<ColorLerp>();
+        world.register::<CycleAnimation>();
+        world.register::<Currency>();
+        world.register::<Equipable>();
+        world.register::<Consumable>();
+        world.insert(SpriteMap::default());
+        let mut raws = RawMaster::empty();
+        raws.load_raws().unwrap();
+        let lantern_id = raws.get_item_id("lantern").unwrap();
+        let base_raws : SharedRaws = Arc::new(raws);
+        world.insert(base_raws.clone());
+        let lantern = ItemBuilder::build_item_with_id(world.create_entity(), lantern_id, (1, 1)).unwrap();
+        assert!(world.read_storage::<Light>().contains(lantern));
+        assert!(world.read_storage::<Equipable>().contains(lantern));
 
+        //a mod turns the lantern into glowing coins
+        let path = std::env::temp_dir().join(format!("polymorph-refresh-{}.json", std::process::id()));
+        fs::write(&path, r##"{
+            "items" : [
+                { "item_type" : "currency", "id" : "lantern", "name" : "Glowing Coins", "weight" : 0.0, "rarity" : 0.1, "value" : 2.0,
+                  "renderable" : { "character_code" : 36, "fg" : "#ffffff", "bg" : "#000000" },
+                  "color_lerp" : { "color_a" : "#ff0000", "color_b" : "#0000ff", "rate" : 1.0, "offset" : 0.0 },
+                  "currency" : { "min" : 3, "max" : 3 } }
+            ]
+        }"##).unwrap();
+        let mut modded = RawMaster::empty();
+        let loaded = modded.load_files(&[(BASE_SOURCE.to_string(), path.clone())]);
+        let _ = fs::remove_file(&path);
+        loaded.unwrap();
+        world.insert::<SharedRaws>(Arc::new(modded));
+        assert!(ItemBuilder::refresh_items(&world).is_empty());
+
+        assert!(!world.read_storage::<Light>().contains(lantern));
+        assert!(!world.read_storage::<Equipable>().contains(lantern));
+        assert!(world.read_storage::<ColorLerp>().contains(lantern));
+        assert_eq!(world.read_storage::<Currency>().get(lantern).map(|currency| currency.amt), Option::from(3));
+        assert_eq!(world.read_storage::<Renderable>().get(lantern).unwrap().glyph, 36);
+
+        //and reloading the base raws puts it back
+        world.insert(base_raws);
+        assert!(ItemBuilder::refresh_items(&world).is_empty());
+        assert!(world.read_storage::<Light>().contains(lantern));
+        assert!(world.read_storage::<Equipable>().contains(lantern));
+        assert!(!world.read_storage::<ColorLerp>().contains(lantern));
+        assert!(!world.read_storage::<Currency>().contains(lantern));
+    }
+}
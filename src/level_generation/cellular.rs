@@ -1,8 +1,10 @@
 use rand::{Rng, StdRng};
 use super::map::tile::TileType;
+use super::map::Seed;
 use super::features::{make_spider_nest, make_camp, FeatureType};
 
 #[derive(Clone)]
+#[allow(dead_code)]
 pub struct Region {
 	pub id: String,
 	pub tiles : Vec<(i32, i32)>,
@@ -35,6 +37,14 @@ pub struct CellularGenerator {
 
 }
 
+/// Random number streams of the generation passes that draw random numbers.
+struct Streams {
+	fill : StdRng,
+	lakes : StdRng,
+	flora : StdRng,
+	nests : StdRng,
+}
+
 impl CellularGenerator {
 	pub fn new (initial_fill : i32, clean_up_threshold : usize, number_of_generations : usize, smoothing : usize) -> Self{
 		CellularGenerator {
@@ -73,8 +83,18 @@ impl CellularGenerator {
 		self.features = features;
 	}
 
-	pub fn generate (&self, width : usize, height : usize, tiles : &mut Vec<TileType>, rng: &mut StdRng) {
-		random_fill(tiles, rng, self.wall, self.floor, self.initial_fill_percent);
+	pub fn generate (&self, width : usize, height : usize, tiles : &mut [TileType], seed : &Seed) {
+		let mut streams = Streams {
+			fill : seed.stream("cellular.fill"),
+			lakes : seed.stream("cellular.lakes"),
+			flora : seed.stream("cellular.flora"),
+			nests : seed.stream("cellular.nests"),
+		};
+		self.generate_with(width, height, tiles, &mut streams);
+	}
+
+	fn generate_with (&self, width : usize, height : usize, tiles : &mut [TileType], streams : &mut Streams) {
+		random_fill(tiles, &mut streams.fill, self.wall, self.floor, self.initial_fill_percent);
 		add_map_edges(width, height, tiles, self.wall);
 		for _i in 0..self.generations {
 			perform_generation(width, height, tiles, self.wall, self.floor);
@@ -104,7 +124,7 @@ impl CellularGenerator {
 		
 		if connect_floor_regions(width, height, tiles, self.floor) {
 			error!("Rejected level; generating again");
-			self.generate(width, height, tiles, rng);
+			self.generate_with(width, height, tiles, streams);
 		} else {
 			clean_up_regions(width, height, tiles, self.wall, self.floor, self.clean_up_threshold);		
 			add_map_edges(width, height, tiles, self.wall);
@@ -112,10 +132,10 @@ impl CellularGenerator {
 			remove_unseen_walls(width, height, tiles, self.wall, self.floor);
 			
 			if self.liquid != TileType::Empty {
-				make_lakes(width, height, self.liquid, self.floor, tiles, &possible_nests, self.min_lake_size, self.max_lake_size, rng);
+				make_lakes(width, height, self.liquid, self.floor, tiles, &possible_nests, self.min_lake_size, self.max_lake_size, &mut streams.lakes);
 			}
 			if self.flora != TileType::Empty {
-				plant(width, height, tiles, rng, self.flora, self.floor, self.floral_density);
+				plant(width, height, tiles, &mut streams.flora, self.flora, self.floor, self.floral_density);
 				grow(width, height, tiles, self.flora, self.floor);
 			}
 
@@ -124,7 +144,7 @@ impl CellularGenerator {
 			}
 
 			for region in possible_nests.clone() {
-				make_spider_nest(width, tiles, region, rng);
+				make_spider_nest(width, tiles, region, &mut streams.nests);
 			}
 			
 			if self.liquid != TileType::Empty {
@@ -134,14 +154,14 @@ impl CellularGenerator {
 	}
 }
 
-fn random_fill (tiles : &mut Vec<TileType>, rng: &mut StdRng, wall : TileType, floor : TileType, initial_fill_percent : i32) {
+fn random_fill (tiles : &mut [TileType], rng: &mut StdRng, wall : TileType, floor : TileType, initial_fill_percent : i32) {
     info!("Seeding level...");
 
-    for i in 0..tiles.len() {
+    for tile in tiles.iter_mut() {
         if rng.gen_range(0, 100) < initial_fill_percent {
-			tiles[i] = wall;
+			*tile = wall;
 		} else {
-			tiles[i] = floor;
+			*tile = floor;
 		}
     }
 
@@ -149,7 +169,7 @@ fn random_fill (tiles : &mut Vec<TileType>, rng: &mut StdRng, wall : TileType, f
 
 /// Performs a single generation of the cellular automata 
 /// according to the B5678/S45678 rule.
-pub fn perform_generation (width : usize, height : usize, tiles : &mut Vec<TileType>, wall : TileType, floor : TileType) {
+pub fn perform_generation (width : usize, height : usize, tiles : &mut [TileType], wall : TileType, floor : TileType) {
 	info!("Generating...");
 
 	for x in 1..width-1 {
@@ -176,7 +196,7 @@ pub fn perform_generation (width : usize, height : usize, tiles : &mut Vec<TileT
 }
 
 ///Removes wall or floor regions that don't meet size requirements.
-pub fn clean_up_regions (width : usize, height : usize, tiles : &mut Vec<TileType>, t : TileType, r : TileType, size_threshold: usize) {
+pub fn clean_up_regions (width : usize, height : usize, tiles : &mut [TileType], t : TileType, r : TileType, size_threshold: usize) {
 	info!("Cleaning...");
 	let mut wall_regions = get_all_regions(width, height, t, tiles);
 
@@ -202,7 +222,7 @@ pub fn clean_up_regions (width : usize, height : usize, tiles : &mut Vec<TileTyp
 }
 
 /// Applies a different cellular ruleset to smooth out jagged edges.
-pub fn smooth (width : usize, height : usize, tiles : &mut Vec<TileType>, wall : TileType, floor : TileType) {
+pub fn smooth (width : usize, height : usize, tiles : &mut [TileType], wall : TileType, floor : TileType) {
 	info!("Smoothing...");
 
 	for x in 1..width-1 {
@@ -218,10 +238,10 @@ pub fn smooth (width : usize, height : usize, tiles : &mut Vec<TileType>, wall :
 	}
 }
 
-pub fn add_map_edges (width : usize, height : usize, tiles : &mut Vec<TileType>, wall : TileType) {
+pub fn add_map_edges (width : usize, height : usize, tiles : &mut [TileType], wall : TileType) {
 	for x in 0..width {
-		if tiles [x + 0 * width] != wall {
-			tiles [x + 0 * width] = wall;
+		if tiles [x] != wall {
+			tiles [x] = wall;
 		}
 		if tiles [x + (height - 1) * width] != wall {
 			tiles[x + (height - 1) * width] = wall;
@@ -229,8 +249,8 @@ pub fn add_map_edges (width : usize, height : usize, tiles : &mut Vec<TileType>,
 	}
 
 	for y in 0..height {
-		if tiles [0 + y * width] != wall {
-			tiles [0 + y * width] = wall;
+		if tiles [y * width] != wall {
+			tiles [y * width] = wall;
 		}
 		if tiles [(width - 1) +y * width] != wall {
 			tiles[(width -1) + y * width] = wall;
@@ -239,21 +259,20 @@ pub fn add_map_edges (width : usize, height : usize, tiles : &mut Vec<TileType>,
 }
 
 /// Forms lakes using cellular automata. Panics if the given type is not a liguid.
-pub fn make_lakes (width : usize, height : usize, liquid_type: TileType, floor : TileType, tiles: &mut Vec<TileType>, marked_regions : &Vec<Region>, min_size : usize, max_size : usize, number_generator: &mut StdRng) {
+#[allow(clippy::too_many_arguments)]
+pub fn make_lakes (width : usize, height : usize, liquid_type: TileType, floor : TileType, tiles: &mut [TileType], marked_regions : &Vec<Region>, min_size : usize, max_size : usize, number_generator: &mut StdRng) {
 	use super::map::tile::{shallow_liquid_variant};
 	info!("Forming Lakes...");
 
 	let shallow_variant = shallow_liquid_variant(liquid_type);
 
-	let mut lake_map : Vec<TileType> = tiles.clone();
+	let mut lake_map : Vec<TileType> = tiles.to_vec();
 
 	//fill place initial liquid tiles
 	for x in 1..width-1 {
 		for y in 1..height-1 {
-			if lake_map[x+y*width] == floor {
-				if number_generator.gen_range (0, 100) < 50 {
-					lake_map[x+y*width] = shallow_variant;
-				} 
+			if lake_map[x+y*width] == floor && number_generator.gen_range (0, 100) < 50 {
+				lake_map[x+y*width] = shallow_variant;
 			}
 		}
 	}
@@ -294,17 +313,15 @@ pub fn make_lakes (width : usize, height : usize, liquid_type: TileType, floor :
 		}
 	);
 
-	let mut foo = 0;
 	// reject lakes if they would intrude on feature regions
 	lakes.retain(|a|
 		{
 			let mut retain = true;
 			for edge_tile in &a.edge_tiles {
 				for region in marked_regions {
-					if region.is_tile_in_region(&edge_tile) {
+					if region.is_tile_in_region(edge_tile) {
 						retain = false;
 						bad_lake_counter += 1;
-						foo += 1;
 						break;
 					} else {
 						continue;
@@ -328,7 +345,7 @@ pub fn make_lakes (width : usize, height : usize, liquid_type: TileType, floor :
 	}
 }
 
-pub fn add_lake_depth (width : usize, height : usize, liquid_type: TileType, tiles: &mut Vec<TileType>) {
+pub fn add_lake_depth (width : usize, height : usize, liquid_type: TileType, tiles: &mut [TileType]) {
 	use super::map::tile::{shallow_liquid_variant, deep_liquid_variant};
 	let shallow_variant = shallow_liquid_variant(liquid_type);
 	let deep_variant = deep_liquid_variant(liquid_type);
@@ -347,7 +364,7 @@ pub fn add_lake_depth (width : usize, height : usize, liquid_type: TileType, til
 
 /// Like `random_fill` but places a type of foliage on `TileType::Floor` according to a given density.
 /// Panics if the given type is not foliage.
-pub fn plant (width : usize, height : usize, tiles: &mut Vec<TileType>, number_generator: &mut StdRng, flora_type: TileType, floor : TileType, density: i32) {
+pub fn plant (width : usize, height : usize, tiles: &mut [TileType], number_generator: &mut StdRng, flora_type: TileType, floor : TileType, density: i32) {
 	use super::map::tile::small_foliage_variant;
 	info!("Planting...");
 
@@ -368,7 +385,7 @@ pub fn plant (width : usize, height : usize, tiles: &mut Vec<TileType>, number_g
 
 /// Performs a cellular generation on a type of foilage. And calculates how far each grass tile is from water
 /// Panics if the given flora_type is not actually foliage.
-pub fn grow (width : usize, height : usize, tiles: &mut Vec<TileType>, flora_type: TileType, floor : TileType) {
+pub fn grow (width : usize, height : usize, tiles: &mut [TileType], flora_type: TileType, floor : TileType) {
 	use super::map::tile::{small_foliage_variant, large_foliage_variant};
 	info!("Growing...");
 
@@ -415,11 +432,9 @@ pub fn grow (width : usize, height : usize, tiles: &mut Vec<TileType>, flora_typ
 						tiles[idx] = floor;
 					}
 				}
-			if tiles[idx] == floor {
-					if amt_large_flora >= 1 {
-						tiles[idx] = TileType::SmallMushroom;
-					} 
-				}
+			if tiles[idx] == floor && amt_large_flora >= 1 {
+				tiles[idx] = TileType::SmallMushroom;
+			}
 			}
 		}
 	}
@@ -438,7 +453,7 @@ pub fn grow (width : usize, height : usize, tiles: &mut Vec<TileType>, flora_typ
 				if current_type == small_variant || current_type == large_variant {
 					for region in shore_regions.clone() {
 						for shore_tile in region.tiles {
-							let distance = ((shore_tile.0 as i32 - x as i32).pow(2) + (shore_tile.1 as i32 - y as i32).pow(2)) as f64;
+							let distance = ((shore_tile.0 - x as i32).pow(2) + (shore_tile.1 - y as i32).pow(2)) as f64;
 							let distance = distance.sqrt() as i32 + 1;
 
 							if distance < best_distance || !possible_connection_found {
@@ -462,17 +477,15 @@ pub fn grow (width : usize, height : usize, tiles: &mut Vec<TileType>, flora_typ
 }
 
 /// Removes wall tiles that cannot be seen by the player.
-pub fn remove_unseen_walls (width : usize, height: usize, tiles : &mut Vec<TileType>, wall : TileType, floor : TileType) {
+pub fn remove_unseen_walls (width : usize, height: usize, tiles : &mut [TileType], wall : TileType, floor : TileType) {
 	debug!("Removing unseen walls");
 
 	let mut flagged_points : Vec<(usize, usize)> = Vec::new();
 
 	for x in 0..width {
 		for y in 0..height {
-			if tiles[x+y*width] == wall {
-				if get_surrounding_neighbor_count(width, height, tiles, floor, x as i32, y as i32) == 0 {
-					flagged_points.push((x,y));
-				}
+			if tiles[x+y*width] == wall && get_surrounding_neighbor_count(width, height, tiles, floor, x as i32, y as i32) == 0 {
+				flagged_points.push((x,y));
 			}
 		}
 	}
@@ -484,13 +497,13 @@ pub fn remove_unseen_walls (width : usize, height: usize, tiles : &mut Vec<TileT
 
 /// Attempts to connect separated floor regions. Returns true if successful, returns false
 /// if the paths calculated are too long.
-pub fn connect_floor_regions (width : usize, height : usize, tiles: &mut Vec<TileType>, floor : TileType) -> bool {
+pub fn connect_floor_regions (width : usize, height : usize, tiles: &mut [TileType], floor : TileType) -> bool {
 	use bracket_lib::prelude::{Bresenham, Point};
 
 	let mut reject_level = false;
 
 	let mut regions = get_all_regions(width, height, floor, tiles);
-	regions.sort_by(|a,b| b.size.cmp(&a.size));
+	regions.sort_by_key(|region| std::cmp::Reverse(region.size));
 
 	if regions.len() > 1 {
 		let current_largest = regions.remove(0);
@@ -532,7 +545,7 @@ pub fn connect_floor_regions (width : usize, height : usize, tiles: &mut Vec<Til
 }
 
 /// Digs a circle of a given radius of floors at a point 
-pub fn dig_circle (width : usize, height : usize, tile : (i32, i32), radius: i32, tiles: &mut Vec<TileType>, floor : TileType) {
+pub fn dig_circle (width : usize, height : usize, tile : (i32, i32), radius: i32, tiles: &mut [TileType], floor : TileType) {
 	for x in -radius..radius {
 		for y in -radius..radius {
 			if x*x + y*y <= radius*radius {
@@ -549,7 +562,7 @@ pub fn dig_circle (width : usize, height : usize, tile : (i32, i32), radius: i32
 }
 
 // Returns a `Vec` of all regions in the map of a given `TileType`.
-pub fn get_all_regions (width : usize, height : usize, tile_type: TileType, tiles: &mut Vec<TileType>) -> Vec<Region> {
+pub fn get_all_regions (width : usize, height : usize, tile_type: TileType, tiles: &mut [TileType]) -> Vec<Region> {
 
 	let mut regions: Vec<Region> = Vec::new();
 	let mut map_flags: Vec<u8> = vec![0; width*height];
@@ -572,14 +585,14 @@ pub fn get_all_regions (width : usize, height : usize, tile_type: TileType, tile
 }
 
 /// Gets a region using flood-fill. The type of region is the same as the `TileType` of the starting tile.
-pub fn get_region (width : usize, height: usize, start_tile: (usize, usize), tiles : &mut Vec<TileType>) -> Region{
+pub fn get_region (width : usize, height: usize, start_tile: (usize, usize), tiles : &mut [TileType]) -> Region{
 	use std::collections::VecDeque;
 	use crate::state::time::get_current_time_millis;
 
 	let mut region_tiles: Vec<(i32,i32)> = Vec::new();
 	let mut map_flags : Vec<u8> = vec![0; width*height];
 
-	let current_tile_type = tiles[start_tile.0 + start_tile.1 * width].clone();
+	let current_tile_type = tiles[start_tile.0 + start_tile.1 * width];
 
 	let mut queue : VecDeque<(i32,i32)> = VecDeque::new();
 
@@ -604,7 +617,7 @@ pub fn get_region (width : usize, height: usize, start_tile: (usize, usize), til
 	}
 	let size = region_tiles.len();
 	let mut edge_tiles = region_tiles.clone();
-	edge_tiles.retain(|a| is_edge_tile(width, height, a.0 as i32, a.1 as i32, tiles));
+	edge_tiles.retain(|a| is_edge_tile(width, height, a.0, a.1, tiles));
 
 	let region = Region {
 		id: format!("{}", get_current_time_millis()),
@@ -619,7 +632,7 @@ pub fn get_region (width : usize, height: usize, start_tile: (usize, usize), til
 }
 
 /// Returns true of the given coordates are of a tile on the edge of a region.
-pub fn is_edge_tile (width: usize, height : usize, x: i32, y: i32, tiles : &mut Vec<TileType>) -> bool {
+pub fn is_edge_tile (width: usize, height : usize, x: i32, y: i32, tiles : &mut [TileType]) -> bool {
 	for dx in (x - 1)..(x + 2) {
 		for dy in (y-1)..(y+2) {
 			if (dx <= 0 || dx >= width as i32) || (dy <= 0 || dy >= height as i32) {
@@ -639,11 +652,11 @@ pub fn is_edge_tile (width: usize, height : usize, x: i32, y: i32, tiles : &mut
 }
 
 /// Returns the number of neighbors that match a given type out of the 8 neighbors of a given tile.
-fn get_surrounding_neighbor_count (width : usize, height : usize, tiles : &mut Vec<TileType>, neighbor_type : TileType, x: i32, y: i32) -> i32 {
+fn get_surrounding_neighbor_count (width : usize, height : usize, tiles : &mut [TileType], neighbor_type : TileType, x: i32, y: i32) -> i32 {
 	let mut count = 0;
 	for dx in (x - 1)..(x + 2) {
 		for dy in (y - 1)..(y + 2) {
-			if !((dx == x) && (dy == y)) && !(dx < 0 || dx >= width as i32) && !(dy < 0 || dy >= height as i32) {
+			if (dx != x || dy != y) && dx >= 0 && dx < width as i32 && dy >= 0 && dy < height as i32 {
 				let idx = (dx + dy * width as i32) as usize;
 				if tiles[idx] == neighbor_type {
 					count += 1;
@@ -8,7 +8,7 @@ pub enum FeatureType {
     NoFeatures,
 }
 
-pub fn make_spider_nest (width : usize, tiles : &mut Vec<TileType>, region : Region, rng : &mut StdRng) {
+pub fn make_spider_nest (width : usize, tiles : &mut [TileType], region : Region, rng : &mut StdRng) {
     debug!("Placing nest...");
     //place thin webs
     for edge_tile in region.edge_tiles.clone() {
@@ -47,7 +47,7 @@ pub fn make_spider_nest (width : usize, tiles : &mut Vec<TileType>, region : Reg
         }
     }
 }
-pub fn make_camp (width : usize, tiles : &mut Vec<TileType>, region : Region) {
+pub fn make_camp (width : usize, tiles : &mut [TileType], region : Region) {
     debug!("Placing camp...");
     //TODO add check for size of region so multiple camps can be added
     let mut center = (0,0);
@@ -3,6 +3,7 @@ use super::cellular;
 use super::features::FeatureType;
 use tile::*;
 use bracket_lib::prelude::RGB;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 pub struct Seed {
@@ -14,7 +15,7 @@ impl Seed {
 	pub fn new (raw_text: String) -> Self {
 		use sha2::{Digest, Sha256};
 		let mut hasher = Sha256::default();
-		hasher.input(&raw_text.as_bytes());
+		hasher.input(raw_text.as_bytes());
 		let result = hasher.result();
 		let mut bytes: [u8; 32] = [0; 32];
 		
@@ -33,9 +34,7 @@ impl Seed {
 	/// Useful for random number generation using the `tcod::random` module.
 	pub fn to_32_bit (&self) -> u32 {
 		let mut bytes : [u8; 4] = [0; 4];
-		for i in 0..4 {
-			bytes[i] = self.hash[i];
-		}
+		bytes.copy_from_slice(&self.hash[..4]);
 
 		return u32::from_be_bytes(bytes);
 	}
@@ -46,9 +45,22 @@ impl Seed {
 	pub fn to_256_bit (&self) -> [u8; 32]{
 		return self.hash;
 	}
+
+	/// Returns the random number stream of one generation pass, seeded from this seed and the name
+	/// of the pass. Each pass draws from its own stream, so adding a pass or changing how many
+	/// numbers one pass draws doesn't change the output of the others.
+	pub fn stream (&self, pass : &str) -> StdRng {
+		use sha2::{Digest, Sha256};
+		let mut hasher = Sha256::default();
+		hasher.input(self.hash);
+		hasher.input(pass.as_bytes());
+		let mut bytes : [u8; 32] = [0; 32];
+		bytes.copy_from_slice(&hasher.result());
+		return SeedableRng::from_seed(bytes);
+	}
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Copy, Clone, Deserialize, Serialize)]
 pub enum MapType {
 	Ruins,
 	Cavern,
@@ -59,6 +71,111 @@ pub enum MapType {
 	Empty,
 }
 
+/// Version of the level generators.
+///
+/// The same seed, map type and version always give the same level. The level depends on the
+/// `StdRng` algorithm of `rand` 0.5, which is why its version is pinned, and on the order in which
+/// each pass draws random numbers from its stream (see `Seed::stream`). Any change that alters the
+/// tiles of a known seed must bump this version and update the fingerprints in the golden tests
+/// below; seed codes from other versions then warn that the level may differ.
+///
+/// * 1: one generator shared by every pass
+/// * 2: a random number stream per pass
+pub const GENERATOR_VERSION : u32 = 2;
+
+impl MapType {
+	/// Map types that have a generator, in the order they are offered to the player.
+	pub const GENERATED : [MapType; 4] = [MapType::MushroomCavern, MapType::Cavern, MapType::Hive, MapType::Empty];
+
+	/// Returns true if maps of this type lie under the open sky.
+	pub fn is_surface (&self) -> bool {
+		return matches!(self, MapType::Ruins | MapType::Swamp);
+	}
+
+	/// Short code of this map type, used in seed codes.
+	pub fn code (&self) -> &'static str {
+		match self {
+			MapType::Ruins => "ru",
+			MapType::Cavern => "ca",
+			MapType::MushroomCavern => "mc",
+			MapType::Swamp => "sw",
+			MapType::Hive => "hi",
+			MapType::Hell => "he",
+			MapType::Empty => "em",
+		}
+	}
+
+	/// Player-facing name of this map type.
+	pub fn name (&self) -> &'static str {
+		match self {
+			MapType::Ruins => "Ruins",
+			MapType::Cavern => "Cavern",
+			MapType::MushroomCavern => "Mushroom Cavern",
+			MapType::Swamp => "Swamp",
+			MapType::Hive => "Hive",
+			MapType::Hell => "Hell",
+			MapType::Empty => "Empty",
+		}
+	}
+
+	/// Finds a generated map type by its code or its name, ignoring case and spaces.
+	pub fn parse (text : &str) -> Option<MapType> {
+		let text = text.replace(['_', '-', ' '], "").to_lowercase();
+		return MapType::GENERATED.iter()
+			.find(|map_type| map_type.code() == text || map_type.name().replace(' ', "").to_lowercase() == text)
+			.copied();
+	}
+}
+
+/// Everything needed to generate the same level again: the seed, the map type and the version of
+/// the generators. Written as a seed code of the form `v<version>-<map type code>-<seed>`, which can
+/// be shared and typed back in to replay the level.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SeedCode {
+	pub seed : String,
+	pub map_type : MapType,
+	pub version : u32,
+}
+
+impl SeedCode {
+	/// A seed for the current generators.
+	pub fn new (seed : String, map_type : MapType) -> Self {
+		SeedCode {
+			seed : seed,
+			map_type : map_type,
+			version : GENERATOR_VERSION,
+		}
+	}
+
+	/// Reads a seed code. Returns `None` if the text isn't one, in which case it can still be used
+	/// as a plain seed.
+	pub fn parse (text : &str) -> Option<SeedCode> {
+		let mut parts = text.trim().splitn(3, '-');
+		let version = parts.next()?.strip_prefix(['v', 'V'])?.parse::<u32>().ok()?;
+		let map_type = MapType::parse(parts.next()?)?;
+		let seed = parts.next()?;
+		if seed.is_empty() {
+			return Option::None;
+		}
+		return Option::from(SeedCode {
+			seed : seed.to_string(),
+			map_type : map_type,
+			version : version,
+		});
+	}
+
+	/// Returns true if this seed was made by the current generators, so it will give the same level.
+	pub fn is_current (&self) -> bool {
+		return self.version == GENERATOR_VERSION;
+	}
+}
+
+impl std::fmt::Display for SeedCode {
+	fn fmt (&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return write!(f, "v{}-{}-{}", self.version, self.map_type.code(), self.seed);
+	}
+}
+
 #[derive(Debug)]
 pub struct Map {
     pub width : usize,
@@ -66,16 +183,18 @@ pub struct Map {
 	pub map_type : MapType,
 	pub raw_seed: String,
 	pub hashed_seed: Seed,
-	pub rng : StdRng,
     pub tiles : Vec<TileType>,
 	pub transparency_map : Vec<f32>,
+	/// How much sky light reaches each tile, from 0.0 (covered) to 1.0 (open sky).
+	pub sky_map : Vec<f32>,
+	/// Tiles the player has seen.
+	pub revealed : Vec<bool>,
 	pub ambient_light : RGB,
 }
 
 impl Default for Map {
 	fn default() -> Self {
 		let hashed_seed = Seed::new(String::from("null").clone());
-		let rng : StdRng = SeedableRng::from_seed(hashed_seed.to_256_bit());
 
 		Map {
             width : 10,
@@ -83,9 +202,10 @@ impl Default for Map {
 			map_type : MapType::Cavern,
 			raw_seed : String::from("null"),
 			hashed_seed : hashed_seed,
-			rng : rng,
             tiles : vec![TileType::Empty; 10*10],
 			transparency_map : vec![0.0; 10*10],
+			sky_map : vec![0.0; 10*10],
+			revealed : vec![false; 10*10],
 			ambient_light : RGB::from_f32(0.0, 0.0, 0.0),
         }
 	}
@@ -95,20 +215,25 @@ impl Map {
     pub fn new (width : usize, height : usize, raw_seed : String, map_type : MapType, ambient_light : RGB) -> Self {
 		
 		let hashed_seed = Seed::new(raw_seed.clone());
-		let number_generator : StdRng = SeedableRng::from_seed(hashed_seed.to_256_bit());
         Map {
             width : width,
 			height : height,
 			map_type : map_type,
 			raw_seed : raw_seed,
 			hashed_seed : hashed_seed,
-			rng : number_generator,
             tiles : vec![TileType::Empty; width*height],
 			transparency_map : vec![0.0; width*height],
+			sky_map : vec![0.0; width*height],
+			revealed : vec![false; width*height],
 			ambient_light : ambient_light,
         }
 	}
 	
+	/// Seed code that generates this map again.
+	pub fn seed_code (&self) -> SeedCode {
+		return SeedCode::new(self.raw_seed.clone(), self.map_type);
+	}
+
 	pub fn generate (&mut self) {
 		info!("Generating map...");
 
@@ -119,12 +244,12 @@ impl Map {
 				generator.set_liquid(TileType::ShallowWater, 20, 400);
 				generator.set_walls_floors(TileType::Wall, TileType::Floor);
 				generator.set_features(FeatureType::CavernFeatures);
-				generator.generate(self.width, self.height, &mut self.tiles, &mut self.rng);
+				generator.generate(self.width, self.height, &mut self.tiles, &self.hashed_seed);
 			},
 			MapType::Hive => {
 				let mut generator = cellular::CellularGenerator::new(40, 1, 2, 0);
 				generator.set_walls_floors(TileType::HiveWall, TileType::HiveFloor);
-				generator.generate(self.width, self.height, &mut self.tiles, &mut self.rng);
+				generator.generate(self.width, self.height, &mut self.tiles, &self.hashed_seed);
 			},
 			MapType::MushroomCavern => {
 				let mut generator = cellular::CellularGenerator::new(35, 11, 3, 1);
@@ -132,13 +257,13 @@ impl Map {
 				generator.set_liquid(TileType::ShallowWater, 40, 350);
 				generator.set_walls_floors(TileType::Wall, TileType::Floor);
 				generator.set_features(FeatureType::CavernFeatures);
-				generator.generate(self.width, self.height, &mut self.tiles, &mut self.rng);
+				generator.generate(self.width, self.height, &mut self.tiles, &self.hashed_seed);
 			},
 			MapType::Empty => {
 				// this is here as an easy way to test things without changing the other stuff
 				let mut generator = cellular::CellularGenerator::new(0, 0, 0, 0);
 				generator.set_walls_floors(TileType::HiveWall, TileType::HiveFloor);
-				generator.generate(self.width, self.height, &mut self.tiles, &mut self.rng);
+				generator.generate(self.width, self.height, &mut self.tiles, &self.hashed_seed);
 			}
 			_ => {},
 		}
@@ -147,13 +272,52 @@ impl Map {
 		for i in 0..self.tiles.len() {
 			self.transparency_map[i] = get_tile_transparency(self.tiles[i]);
 		}
+
+		//surface maps start fully open to the sky; generators can roof over tiles or punch windows afterwards.
+		let sky = if self.map_type.is_surface() {
+			1.0
+		} else {
+			0.0
+		};
+		for i in 0..self.tiles.len() {
+			self.sky_map[i] = sky;
+		}
+	}
+
+	/// Sets how much sky light reaches a tile, from 0.0 (covered) to 1.0 (open sky). Used for
+	/// roofs, windows and roofless rooms.
+	pub fn set_sky_exposure (&mut self, x : usize, y : usize, exposure : f32) {
+		if x < self.width && y < self.height {
+			self.sky_map[x + y * self.width] = exposure.clamp(0.0, 1.0);
+		}
+	}
+
+	/// Returns a hash of the tiles, as 16 hex digits. Two maps with the same fingerprint have the
+	/// same tiles; used to check that a seed still generates the same level. Each tile is hashed
+	/// as its `tile::fingerprint_code` followed by its payload, if any, so renaming a variant or
+	/// changing its `Debug` output does not change the fingerprint.
+	pub fn fingerprint (&self) -> String {
+		use sha2::{Digest, Sha256};
+		let mut hasher = Sha256::default();
+		hasher.input((self.width as u32).to_be_bytes());
+		hasher.input((self.height as u32).to_be_bytes());
+		for tile in self.tiles.iter() {
+			hasher.input([tile::fingerprint_code(*tile)]);
+			match tile {
+				TileType::ShortGrass(i) | TileType::TallGrass(i) => hasher.input(i.to_be_bytes()),
+				_ => {}
+			}
+		}
+		let result = hasher.result();
+		return result.iter().take(8).map(|byte| format!("{:02x}", byte)).collect();
 	}
 }
 
 pub mod tile {
 	
-	#[derive(Copy, Clone, Debug, PartialEq)]
+	#[derive(Copy, Clone, Debug, PartialEq, Default)]
 	pub enum TileType {
+		#[default]
 		Empty,
 		Floor,
 		Wall,
@@ -180,20 +344,40 @@ pub mod tile {
 		HiveFloor,
 	}
 
-	impl Default for TileType {
-		fn default() -> Self { TileType::Empty }
+	/// Returns the byte a tile type is hashed as in `Map::fingerprint`. These codes must never
+	/// change; a new variant gets the next unused code.
+	pub fn fingerprint_code (tile_type : TileType) -> u8 {
+		return match tile_type {
+			TileType::Empty => 0,
+			TileType::Floor => 1,
+			TileType::Wall => 2,
+			TileType::ShallowWater => 3,
+			TileType::DeepWater => 4,
+			TileType::ShallowLava => 5,
+			TileType::DeepLava => 6,
+			TileType::ShortGrass(_i) => 7,
+			TileType::TallGrass(_i) => 8,
+			TileType::SmallMushroom => 9,
+			TileType::LargeMushroom => 10,
+			TileType::ThickWebs => 11,
+			TileType::ThinWebs => 12,
+			TileType::EggSac => 13,
+			TileType::Fire => 14,
+			TileType::CampSeat => 15,
+			TileType::TentTopRight => 16,
+			TileType::TentTopLeft => 17,
+			TileType::TentTopCenter => 18,
+			TileType::TentBottomCenter => 19,
+			TileType::TentBottomLeft => 20,
+			TileType::TentBottomRight => 21,
+			TileType::HiveWall => 22,
+			TileType::HiveFloor => 23,
+		};
 	}
 
 	/// Returns true if the given tile type is safe for spawning the player and for pathing
 	pub fn is_safe (tile_type : TileType) -> bool {
-		match tile_type {
-			TileType::Wall => false,
-			TileType::HiveWall => false,
-			TileType::Fire => false,
-			TileType::DeepLava | TileType::ShallowLava => false,
-			TileType::Empty => false,
-			_ => true,
-		}
+		return !matches!(tile_type, TileType::Wall | TileType::HiveWall | TileType::Fire | TileType::DeepLava | TileType::ShallowLava | TileType::Empty);
 	}
 
 	/// Returns the large variant of a tile type if it is foliage. Panics otherwise.
@@ -244,6 +428,41 @@ pub mod tile {
 		}
 	}
 
+	/// Returns the name of a tile type as shown to the player.
+	pub fn get_tile_name (tile_type : TileType) -> &'static str {
+		match tile_type {
+			TileType::Empty => "Nothing",
+			TileType::Floor | TileType::HiveFloor => "Floor",
+			TileType::Wall => "Wall",
+			TileType::HiveWall => "Hive wall",
+			TileType::ShallowWater => "Shallow water",
+			TileType::DeepWater => "Deep water",
+			TileType::ShallowLava => "Shallow lava",
+			TileType::DeepLava => "Deep lava",
+			TileType::ShortGrass(_d) => "Short grass",
+			TileType::TallGrass(_d) => "Tall grass",
+			TileType::SmallMushroom => "Small mushroom",
+			TileType::LargeMushroom => "Large mushroom",
+			TileType::ThickWebs => "Thick webs",
+			TileType::ThinWebs => "Thin webs",
+			TileType::EggSac => "Egg sac",
+			TileType::Fire => "Campfire",
+			TileType::CampSeat => "Log seat",
+			TileType::TentTopRight | TileType::TentTopLeft | TileType::TentTopCenter
+				| TileType::TentBottomCenter | TileType::TentBottomLeft | TileType::TentBottomRight => "Tent",
+		}
+	}
+
+	/// Returns what kind of notable place a tile belongs to, if any.
+	pub fn feature_name (tile_type : TileType) -> Option<&'static str> {
+		match tile_type {
+			TileType::CampSeat | TileType::Fire | TileType::TentTopRight | TileType::TentTopLeft | TileType::TentTopCenter
+				| TileType::TentBottomCenter | TileType::TentBottomLeft | TileType::TentBottomRight => Option::from("camp"),
+			TileType::EggSac | TileType::ThickWebs => Option::from("nest"),
+			_ => Option::None,
+		}
+	}
+
 	pub fn get_tile_transparency (tile_type : TileType) -> f32 {
 		match tile_type {
 			TileType::Wall => 1.0,
@@ -258,4 +477,59 @@ pub mod tile {
 			_ => 0.0,
 		}
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand::Rng;
+
+	/// Fingerprints of known seeds, generated at the map size the game uses. If one of these fails,
+	/// the generators no longer make the same levels: bump `GENERATOR_VERSION` and update them.
+	const GOLDEN : [(MapType, &str, &str); 10] = [
+		(MapType::MushroomCavern, "adsfasds", "ad5ec06768c84c3d"),
+		(MapType::MushroomCavern, "polymorph", "7c0e32e0b678a806"),
+		(MapType::MushroomCavern, "12345", "1b155b138e5e05fb"),
+		(MapType::Cavern, "adsfasds", "016b851fe6370baf"),
+		(MapType::Cavern, "polymorph", "180ac008cc85ffa5"),
+		(MapType::Cavern, "12345", "be0cf092e49cab5b"),
+		(MapType::Hive, "adsfasds", "f6f92cd73e941cbb"),
+		(MapType::Hive, "polymorph", "0fad8a1e89e875b8"),
+		(MapType::Hive, "12345", "c016c21514a841d1"),
+		(MapType::Empty, "adsfasds", "167bb57481648da5"),
+	];
+
+	#[test]
+	fn known_seeds_generate_the_same_levels () {
+		assert_eq!(GENERATOR_VERSION, 2, "update the golden fingerprints along with the version");
+		for (map_type, seed, fingerprint) in GOLDEN.iter() {
+			let mut map = Map::new(100, 100, seed.to_string(), *map_type, RGB::from_f32(0.0, 0.0, 0.0));
+			map.generate();
+			assert_eq!(map.fingerprint(), *fingerprint, "{:?} level for seed \"{}\" changed", map_type, seed);
+		}
+	}
+
+	#[test]
+	fn passes_draw_from_separate_streams () {
+		let seed = Seed::new("adsfasds".to_string());
+		let draw = |pass : &str| -> Vec<u32> {
+			let mut rng = seed.stream(pass);
+			return (0..8).map(|_| rng.gen()).collect();
+		};
+		let fill = draw("cellular.fill");
+		let again = draw("cellular.fill");
+		let lakes = draw("cellular.lakes");
+		assert_eq!(fill, again);
+		assert_ne!(fill, lakes);
+	}
+
+	#[test]
+	fn seed_codes_round_trip () {
+		let code = SeedCode::new("a-b c".to_string(), MapType::Hive);
+		assert_eq!(code.to_string(), format!("v{}-hi-a-b c", GENERATOR_VERSION));
+		assert_eq!(SeedCode::parse(&code.to_string()), Option::from(code));
+		assert_eq!(SeedCode::parse("v1-MushroomCavern-adsfasds").map(|code| code.map_type), Option::from(MapType::MushroomCavern));
+		assert_eq!(SeedCode::parse("adsfasds"), Option::None);
+		assert_eq!(SeedCode::parse("v1-mc-"), Option::None);
+	}
 }
\ No newline at end of file
@@ -0,0 +1,11 @@
+//! Parts of the game that the tools in `src/bin` use as well.
+
+// serde's derives expand to impls and cfgs that newer compilers flag; nothing we can change from here.
+#![allow(non_local_definitions, unexpected_cfgs)]
+// House style, as in the game itself.
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::single_match)]
+
+#[macro_use]
+extern crate log;
+
+pub mod raw;
@@ -0,0 +1,197 @@
+//! Text shown to the player, looked up by key in a string table for the chosen language.
+//!
+//! Tables are JSON objects mapping keys to text, one file per language in `lang/`, e.g.
+//! `lang/en.json`. English is always loaded first, so a translation only needs the keys it changes.
+//! Text may contain `{0}`, `{1}` and so on, which are replaced by the arguments given to `format`.
+//! Items are translated under `item.<name>`, their types under `item_type.<type>`, equipment slots
+//! under `equip_slot.<slot>`, tiles under `tile.<name>` and map types under `map_type.<code>`;
+//! without a translation the name from the raws or the game is shown.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use bracket_lib::prelude::to_cp437;
+
+use crate::raw::{ItemRaw, ItemType, EquipSlot};
+use crate::level_generation::map::MapType;
+
+const LANG_DIR : &str = "lang";
+/// Language whose table every other language falls back on.
+pub const DEFAULT_LANGUAGE : &str = "en";
+
+/// Loaded strings, shared by every state and inserted into each world as a resource.
+pub type SharedStrings = Arc<Strings>;
+
+#[derive(Default)]
+pub struct Strings {
+    pub language : String,
+    table : HashMap<String, String>,
+}
+
+impl Strings {
+    /// Loads the English table and then the given language over it. Missing or broken files are
+    /// logged and skipped, so there is always something to show.
+    pub fn load (language : &str) -> Self {
+        let mut strings = Strings {
+            language : language.to_string(),
+            table : HashMap::new(),
+        };
+        strings.load_table(DEFAULT_LANGUAGE);
+        if language != DEFAULT_LANGUAGE {
+            strings.load_table(language);
+        }
+        return strings;
+    }
+
+    fn load_table (&mut self, language : &str) {
+        let file = Path::new(LANG_DIR).join(format!("{}.json", language));
+        let serialized = match fs::read_to_string(&file) {
+            Ok(t) => t,
+            Err(e) => {
+                error!("Unable to read string table {} : {}", file.display(), e);
+                return;
+            },
+        };
+        let table : HashMap<String, String> = match serde_json::from_str(&serialized) {
+            Ok(t) => t,
+            Err(e) => {
+                error!("Unable to parse string table {} : {}", file.display(), e);
+                return;
+            },
+        };
+        for (key, text) in table.into_iter() {
+            self.table.insert(key, displayable(&text));
+        }
+        info!("Loaded {} strings for language \"{}\"", self.table.len(), language);
+    }
+
+    /// Returns the text for a key, or the key itself if no table has it.
+    pub fn get<'a> (&'a self, key : &'a str) -> &'a str {
+        match self.table.get(key) {
+            Some(text) => return text,
+            None => {
+                debug!("No string for \"{}\"", key);
+                return key;
+            },
+        }
+    }
+
+    /// Returns the text for a key with `{0}`, `{1}`... replaced by the arguments.
+    pub fn format (&self, key : &str, args : &[&dyn Display]) -> String {
+        let mut text = self.get(key).to_string();
+        for (idx, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", idx), &arg.to_string());
+        }
+        return text;
+    }
+
+    /// Returns the text for a key, or `fallback` made displayable if no table has it.
+    fn get_or (&self, key : &str, fallback : &str) -> String {
+        match self.table.get(key) {
+            Some(text) => return text.clone(),
+            None => return displayable(fallback),
+        }
+    }
+
+    /// Display name of an item, falling back on the name given in the raws.
+    pub fn item_name (&self, item : &ItemRaw) -> String {
+        return self.get_or(&format!("item.{}", item.id), &item.name);
+    }
+
+    /// Display name of an item type.
+    pub fn item_type_name (&self, item_type : ItemType) -> String {
+        return self.get_or(&format!("item_type.{}", item_type.key()), item_type.name());
+    }
+
+    /// Display name of an equipment slot.
+    pub fn equip_slot_name (&self, slot : EquipSlot) -> String {
+        return self.get_or(&format!("equip_slot.{}", slot.key()), slot.name());
+    }
+
+    /// Display name of a map tile.
+    pub fn tile_name (&self, name : &str) -> String {
+        return self.get_or(&format!("tile.{}", name), name);
+    }
+
+    /// Display name of a map type.
+    pub fn map_type_name (&self, map_type : MapType) -> String {
+        return self.get_or(&format!("map_type.{}", map_type.code()), map_type.name());
+    }
+}
+
+/// Languages that have a string table in `lang/`, in alphabetical order.
+pub fn available_languages () -> Vec<String> {
+    let mut languages : Vec<String> = match fs::read_dir(LANG_DIR) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .collect(),
+        Err(e) => {
+            error!("Unable to list languages in {} : {}", LANG_DIR, e);
+            Vec::new()
+        },
+    };
+    if !languages.iter().any(|language| language == DEFAULT_LANGUAGE) {
+        languages.push(DEFAULT_LANGUAGE.to_string());
+    }
+    languages.sort();
+    return languages;
+}
+
+/// Replaces characters the code page 437 fonts can't draw with the closest ones they can, or `?`.
+pub fn displayable (text : &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if to_cp437(c) != 0 {
+            result.push(c);
+            continue;
+        }
+        match fallback(c) {
+            Some(replacement) => result.push_str(replacement),
+            None => result.push('?'),
+        }
+    }
+    return result;
+}
+
+/// Look-alikes for common characters missing from code page 437.
+fn fallback (c : char) -> Option<&'static str> {
+    let replacement = match c {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ā' | 'Ą' => "A",
+        'ã' | 'ā' | 'ą' => "a",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'ć' | 'č' => "c",
+        'È' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'ē' | 'ę' | 'ě' => "e",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'Ł' => "L",
+        'ł' => "l",
+        'Ń' | 'Ň' => "N",
+        'ń' | 'ň' => "n",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' | 'Ő' => "O",
+        'õ' | 'ø' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ř' => "R",
+        'ř' => "r",
+        'Ś' | 'Š' => "S",
+        'ś' | 'š' => "s",
+        'Ù' | 'Ú' | 'Û' | 'Ű' | 'Ů' => "U",
+        'ű' | 'ů' => "u",
+        'Ý' | 'Ÿ' => "Y",
+        'ý' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        '‘' | '’' | '‚' | '′' => "'",
+        '“' | '”' | '„' | '″' => "\"",
+        '–' | '—' | '‐' => "-",
+        '…' => "...",
+        '\u{a0}' => " ",
+        _ => return Option::None,
+    };
+    return Option::from(replacement);
+}
+
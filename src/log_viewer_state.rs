@@ -0,0 +1,99 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, Rect, Point, ColorPair, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime};
+use crate::game_log::LogEntry;
+use crate::components::gui::{Panel, Justification};
+use crate::widgets::{ScrollView, draw_window, FRAME_FG, FRAME_BG};
+use crate::locale::SharedStrings;
+
+/// Full screen view of the whole message history.
+pub struct LogViewerState {
+    entries : Vec<LogEntry>,
+    scroll : usize,
+    strings : SharedStrings,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl LogViewerState {
+    pub fn new (entries : Vec<LogEntry>, strings : SharedStrings) -> Self {
+        LogViewerState {
+            entries : entries,
+            scroll : 0,
+            strings : strings,
+            draw_batch : DrawBatch::new(),
+        }
+    }
+}
+
+impl LogViewerState {
+    fn view (&self) -> ScrollView {
+        let lines = self.entries.iter().map(|entry| (format!("{:>5} {}", entry.turn, entry.display_text()), entry.color())).collect();
+        return ScrollView::new(lines).anchored_bottom();
+    }
+
+    /// The window the history is drawn in, over the game.
+    fn window (ctx : &BTerm) -> Panel {
+        let screen_size = ctx.get_char_size();
+        return Panel::new(Rect::with_size(2, 2, screen_size.0 as i32 - 5, screen_size.1 as i32 - 4), true, Justification::CENTER, Option::None);
+    }
+
+    fn area (&self, ctx : &BTerm) -> Panel {
+        let window = LogViewerState::window(ctx);
+        return window.child(Rect::with_size(1, 0, window.content().width() - 1, window.content().height()));
+    }
+}
+
+impl State for LogViewerState {
+
+    fn init (&mut self) {}
+
+    fn on_enter (&mut self) {}
+
+    fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        match input.key {
+            Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::M) => {
+                return StateAction::Pop(Option::from("Closed message history.".to_string()));
+            },
+            _ => {},
+        }
+        let mut view = self.view().with_scroll(self.scroll);
+        view.update(&input, &self.area(ctx));
+        self.scroll = view.scroll;
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let window = LogViewerState::window(ctx);
+
+        self.draw_batch.target(0);
+        draw_window(&mut self.draw_batch, &window, self.strings.get("log_viewer.title"));
+        let hint = format!(" {} ", self.strings.get("log_viewer.help"));
+        self.draw_batch.print_color(Point::new(window.bounds.x1 + (window.width() - hint.chars().count() as i32) / 2, window.bounds.y2 - 1), hint, ColorPair::new(FRAME_FG, FRAME_BG));
+
+        //newest message at the bottom
+        let area = self.area(ctx);
+        self.view().with_scroll(self.scroll).draw(&mut self.draw_batch, &area);
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+
+    fn is_transparent (&self) -> bool {
+        return true;
+    }
+}
@@ -1,3 +1,7 @@
+// specs-derive expands to impls that newer compilers flag; nothing we can change from here.
+#![allow(non_local_definitions, unexpected_cfgs)]
+// House style: explicit returns, `field : field` initializers and `match` rather than `if let`.
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::single_match)]
 #[macro_use]
 extern crate log;
 extern crate simplelog;
@@ -6,8 +10,6 @@ extern crate toml;
 extern crate bracket_lib;
 extern crate specs;
 #[macro_use]
-extern crate lazy_static;
-#[macro_use]
 extern crate specs_derive;
 extern crate object_pool;
 extern crate glutin;
@@ -18,8 +20,19 @@ pub mod components;
 pub mod systems;
 pub mod item;
 pub mod test_state;
-pub mod raw;
 pub mod level_generation;
+pub mod tileset;
+pub mod game_log;
+pub mod log_viewer_state;
+pub mod widgets;
+pub mod inventory_state;
+pub mod main_menu_state;
+pub mod new_game_state;
+pub mod character_creation_state;
+pub mod game_over_state;
+pub mod settings_state;
+pub mod input;
+pub mod locale;
 
 use simplelog::*;
 use std::fs::File;
@@ -27,10 +40,18 @@ use bracket_lib::prelude::*;
 use bracket_lib::prelude::BTerm;
 use bracket_lib::prelude::BTermBuilder;
 use state::*;
-use test_state::TestState;
-use raw::RAW;
+use main_menu_state::MainMenuState;
+use character_creation_state::CharacterCreationState;
+use level_generation::map::{SeedCode, MapType};
+use std::sync::Arc;
+use polymorph::raw;
+use raw::{RawMaster, RawError};
+use locale::Strings;
+
+const USAGE : &str = "Usage: Polymorph [--seed <seed or seed code>] [--map-type <map type>]";
 
 fn main() {
+    let start_seed = parse_args();
     let settings_context = config::load_config_file();
 
     let filter : LevelFilter = if settings_context.settings.development.debug {
@@ -54,35 +75,84 @@ fn main() {
         info!("{}", settings_context.message);
     }
 
-    let mut context : BTerm= BTermBuilder::new()
+    let graphical = &settings_context.settings.graphical;
+    let tilesets = if graphical.tilesets.is_empty() {
+        warn!("No tilesets in config; using the built in ones");
+        tileset::default_tilesets()
+    } else {
+        graphical.tilesets.clone()
+    };
+    let map_tileset = tileset::find_tileset(&tilesets, &graphical.tileset);
+    let text_font = tileset::find_tileset(&tilesets, &graphical.text_font);
+    info!("Using tileset \"{}\" and text font \"{}\"", map_tileset.name, text_font.name);
+
+    let layout = config::ConsoleLayout::new(graphical.console.clone(), graphical.console.tile_size(&map_tileset));
+    let mut builder = BTermBuilder::new()
         .with_dimensions(40, 20)
-        .with_tile_dimensions(8, 12)
+        .with_tile_dimensions(layout.tile_size.0, layout.tile_size.1)
         .with_title("Polymorph")
         .with_resource_path("assets")        
-        .with_fullscreen(settings_context.settings.graphical.fullscreen)
-        .with_font("terminal.png", 8, 12)
-        .with_vsync(settings_context.settings.graphical.vsync)
-        .with_simple_console(80, 40, "terminal.png")
-        .with_simple_console_no_bg(80, 40, "terminal.png")
+        .with_fullscreen(graphical.fullscreen)
+        .with_font(map_tileset.file.clone(), map_tileset.tile_width, map_tileset.tile_height)
+        .with_vsync(graphical.vsync);
+    if text_font.file != map_tileset.file {
+        builder = builder.with_font(text_font.file.clone(), text_font.tile_width, text_font.tile_height);
+    }
+    let mut context : BTerm = builder
+        .with_simple_console(graphical.console.width, graphical.console.height, map_tileset.file.clone())
+        .with_simple_console_no_bg(graphical.console.width, graphical.console.height, text_font.file.clone())
         .build().unwrap();
 
-    let console_dimensions = config::auto_detect_resolution();
-    context.set_char_size(console_dimensions.0, console_dimensions.1);
-    context.set_active_console(1);
-    context.set_char_size(console_dimensions.0, console_dimensions.1);
-    context.set_active_console(0);
+    let resolution = if graphical.console.auto {
+        config::detect_resolution()
+    } else {
+        Option::None
+    };
+    if graphical.console.auto && resolution.is_none() {
+        warn!("Falling back to the configured console size of {} x {}", graphical.console.width, graphical.console.height);
+    }
+    layout.resize_consoles(&mut context, layout.console_size(resolution));
 
     context.post_scanlines = settings_context.settings.graphical.post_processing.scan_lines;
     context.post_screenburn = settings_context.settings.graphical.post_processing.screen_burn;
 
-    RAW.lock().unwrap().load_raws();
+    let mut raws = RawMaster::empty();
+    let raw_result = raws.load_raws();
+    match raw_result {
+        Ok(_v) => info!("Loaded raws"),
+        Err(RawError::Invalid(problems)) => {
+            for problem in problems.iter() {
+                error!("{}", problem);
+            }
+            warn!("Loaded raws with {} problem(s); affected items may not work correctly", problems.len());
+        },
+        Err(e) => {
+            error!("{}", e);
+            return;
+        },
+    }
+    let strings = Strings::load(&settings_context.settings.other.language);
+
     if !settings_context.settings.graphical.fullscreen {
-        BACKEND.lock().context_wrapper.as_ref().unwrap().wc.window().set_maximized(true);
+        match BACKEND.lock().context_wrapper.as_ref() {
+            Some(context_wrapper) => context_wrapper.wc.window().set_maximized(true),
+            None => warn!("Unable to maximize the window"),
+        }
     }
 
     debug!("Creating Manager");
-    let mut gs: Manager = Manager::new();
-    gs.push(Box::new(TestState::new(&mut context)), Option::from(format!("Initial state.")));
+    let mut gs: Manager = Manager::new().with_layout(layout);
+    let raws = Arc::new(raws);
+    let strings = Arc::new(strings);
+    gs.push(Box::new(MainMenuState::new(settings_context.settings.clone(), map_tileset.clone(), raws.clone(), strings.clone())), Option::from("Initial state.".to_string()));
+    //a seed from the command line skips the menus and goes straight to character creation
+    match start_seed {
+        Some(seed) => {
+            info!("Starting with seed code {} from the command line", seed);
+            gs.push(Box::new(CharacterCreationState::new(settings_context.settings.clone(), map_tileset, raws, strings, seed)), Option::from("Seed from the command line.".to_string()));
+        },
+        None => {},
+    }
     debug!("Starting main loop");
     let main_loop_result = main_loop(context, gs);
     match main_loop_result {
@@ -91,4 +161,46 @@ fn main() {
             error!("Error initializing main loop : {}", e);
         },
     }
-}
\ No newline at end of file
+}
+/// Reads `--seed` and `--map-type` from the command line. Returns the seed to start a game with,
+/// if either was given. Exits with the usage on anything it doesn't understand.
+fn parse_args () -> Option<SeedCode> {
+    let mut seed : Option<String> = Option::None;
+    let mut map_type : Option<MapType> = Option::None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--seed", Some(value)) => seed = Option::from(value),
+            ("--map-type", Some(value)) => {
+                match MapType::parse(&value) {
+                    Some(t) => map_type = Option::from(t),
+                    None => {
+                        let names : Vec<&str> = MapType::GENERATED.iter().map(|t| t.code()).collect();
+                        eprintln!("Unknown map type \"{}\"; expected one of {}\n{}", value, names.join(", "), USAGE);
+                        std::process::exit(2);
+                    },
+                }
+            },
+            _ => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            },
+        }
+    }
+
+    match (seed, map_type) {
+        (None, None) => return Option::None,
+        (Some(seed), map_type) => {
+            match SeedCode::parse(&seed) {
+                Some(code) => {
+                    if map_type.is_some_and(|t| t != code.map_type) {
+                        eprintln!("Ignoring --map-type; the seed code {} has its own", code);
+                    }
+                    return Option::from(code);
+                },
+                None => return Option::from(SeedCode::new(seed, map_type.unwrap_or(MapType::GENERATED[0]))),
+            }
+        },
+        (None, Some(map_type)) => return Option::from(SeedCode::new(new_game_state::random_seed(), map_type)),
+    }
+}
@@ -0,0 +1,113 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, Rect, Point, ColorPair, RGB, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime, StateResult};
+use crate::settings_state::SettingsState;
+use crate::components::gui::{Panel, Justification};
+use crate::config::Settings;
+use crate::tileset::Tileset;
+use crate::raw::SharedRaws;
+use crate::locale::SharedStrings;
+use crate::new_game_state::NewGameState;
+use crate::widgets::{Label, ListView};
+
+//there is no Continue entry until games can be saved; see "Not yet implemented" in the README.
+const NEW_GAME : usize = 0;
+const SETTINGS : usize = 1;
+const QUIT : usize = 2;
+
+/// Title screen shown when the game starts and after a game ends.
+pub struct MainMenuState {
+    settings : Settings,
+    map_tileset : Tileset,
+    raws : SharedRaws,
+    strings : SharedStrings,
+    menu : ListView,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl MainMenuState {
+    pub fn new (settings : Settings, map_tileset : Tileset, raws : SharedRaws, strings : SharedStrings) -> Self {
+        let menu = ListView::new(vec![strings.get("menu.new_game").to_string(), strings.get("menu.settings").to_string(), strings.get("menu.quit").to_string()]);
+        MainMenuState {
+            settings : settings,
+            map_tileset : map_tileset,
+            raws : raws,
+            strings : strings,
+            menu : menu,
+            draw_batch : DrawBatch::new(),
+        }
+    }
+
+    fn menu_area (ctx : &BTerm) -> Panel {
+        let screen_size = ctx.get_char_size();
+        let x = screen_size.0 as i32 / 2 - 6;
+        let y = screen_size.1 as i32 / 2;
+        return Panel::new(Rect::with_size(x, y, 12, 3), false, Justification::CENTER, Option::None);
+    }
+}
+
+impl State for MainMenuState {
+
+    fn init (&mut self) {}
+
+    fn on_enter (&mut self) {}
+
+    fn on_resume (&mut self, result : StateResult) {
+        match result {
+            StateResult::Settings(settings) => self.settings = *settings,
+            StateResult::Raws(raws) => self.raws = raws,
+            _ => {},
+        }
+    }
+
+    fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        if input.key == Some(VirtualKeyCode::Escape) {
+            return StateAction::Exit;
+        }
+
+        match self.menu.update(&input, &MainMenuState::menu_area(ctx)) {
+            Some(NEW_GAME) => {
+                let new_game = NewGameState::new(self.settings.clone(), self.map_tileset.clone(), self.raws.clone(), self.strings.clone());
+                return StateAction::Push(Box::new(new_game), Option::from("Starting a new game.".to_string()));
+            },
+            Some(SETTINGS) => {
+                return StateAction::Push(Box::new(SettingsState::new(self.settings.clone(), self.strings.clone())), Option::from("Opened settings.".to_string()));
+            },
+            Some(QUIT) => return StateAction::Exit,
+            _ => {},
+        }
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let screen_size = ctx.get_char_size();
+        let title_color = ColorPair::new(RGB::from_u8(90, 200, 255), RGB::from_u8(0, 0, 0));
+
+        self.draw_batch.target(0);
+        self.draw_batch.cls();
+        self.draw_batch.target(1);
+        self.draw_batch.cls();
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 - 4, "P O L Y M O R P H", title_color);
+        self.menu.draw(&mut self.draw_batch, &MainMenuState::menu_area(ctx));
+        Label::new(self.strings.get("menu.help")).draw(&mut self.draw_batch, &Panel::new(Rect::with_size(1, screen_size.1 as i32 - 2, 40, 1), false, Justification::FREE, Option::None));
+        self.draw_batch.print(Point::new(screen_size.0 as i32 - 8, screen_size.1 as i32 - 2), format!("v{}", env!("CARGO_PKG_VERSION")));
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+}
@@ -0,0 +1,118 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, Rect, ColorPair, RGB, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime};
+use crate::components::gui::{Panel, Justification};
+use crate::config::Settings;
+use crate::tileset::Tileset;
+use crate::raw::SharedRaws;
+use crate::locale::SharedStrings;
+use crate::character_creation_state::CharacterCreationState;
+use crate::widgets::{Label, TextInput};
+use crate::level_generation::map::{SeedCode, MapType};
+
+/// Asks the player for the seed and map type of a new game. A seed code entered as the seed
+/// brings its own map type.
+pub struct NewGameState {
+    settings : Settings,
+    map_tileset : Tileset,
+    raws : SharedRaws,
+    strings : SharedStrings,
+    seed_input : TextInput,
+    /// Index into `MapType::GENERATED`.
+    map_type : usize,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl NewGameState {
+    pub fn new (settings : Settings, map_tileset : Tileset, raws : SharedRaws, strings : SharedStrings) -> Self {
+        NewGameState {
+            settings : settings,
+            map_tileset : map_tileset,
+            raws : raws,
+            strings : strings,
+            seed_input : TextInput::new(32),
+            map_type : 0,
+            draw_batch : DrawBatch::new(),
+        }
+    }
+
+    fn input_area (ctx : &BTerm) -> Panel {
+        let screen_size = ctx.get_char_size();
+        return Panel::new(Rect::with_size(screen_size.0 as i32 / 2 - 17, screen_size.1 as i32 / 2, 34, 1), false, Justification::CENTER, Option::None);
+    }
+}
+
+/// Makes up a seed for players who don't enter one.
+pub fn random_seed () -> String {
+    use rand::Rng;
+    use rand::distributions::Alphanumeric;
+    return rand::thread_rng().sample_iter(&Alphanumeric).take(12).collect();
+}
+
+impl State for NewGameState {
+
+    fn init (&mut self) {}
+
+    fn on_enter (&mut self) {}
+
+    fn update (&mut self, _ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        if input.key == Some(VirtualKeyCode::Escape) {
+            return StateAction::Pop(Option::from("Cancelled new game.".to_string()));
+        }
+        let map_types = MapType::GENERATED.len();
+        match input.key {
+            Some(VirtualKeyCode::Left) => self.map_type = (self.map_type + map_types - 1) % map_types,
+            Some(VirtualKeyCode::Right) => self.map_type = (self.map_type + 1) % map_types,
+            _ => {},
+        }
+        if self.seed_input.update(&input) {
+            let text = self.seed_input.text.trim();
+            let seed = match SeedCode::parse(text) {
+                Some(seed) => seed,
+                None if text.is_empty() => SeedCode::new(random_seed(), MapType::GENERATED[self.map_type]),
+                None => SeedCode::new(text.to_string(), MapType::GENERATED[self.map_type]),
+            };
+            let creation = CharacterCreationState::new(self.settings.clone(), self.map_tileset.clone(), self.raws.clone(), self.strings.clone(), seed);
+            return StateAction::Switch(Box::new(creation), Option::from("Seed chosen.".to_string()));
+        }
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let screen_size = ctx.get_char_size();
+        let text_color = ColorPair::new(RGB::from_u8(255, 255, 255), RGB::from_u8(0, 0, 0));
+        let input_area = NewGameState::input_area(ctx);
+
+        self.draw_batch.target(0);
+        self.draw_batch.cls();
+        self.draw_batch.target(1);
+        self.draw_batch.cls();
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 - 3, self.strings.get("new_game.title"), text_color);
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 - 1, self.strings.get("new_game.prompt"), text_color);
+        self.seed_input.draw(&mut self.draw_batch, &input_area);
+        let map_type = match SeedCode::parse(&self.seed_input.text) {
+            Some(seed) => self.strings.format("new_game.map_from_seed_code", &[&self.strings.map_type_name(seed.map_type)]),
+            None => self.strings.format("new_game.map", &[&self.strings.map_type_name(MapType::GENERATED[self.map_type])]),
+        };
+        self.draw_batch.print_color_centered(screen_size.1 as i32 / 2 + 2, map_type, text_color);
+        Label::new(self.strings.get("new_game.help")).draw(&mut self.draw_batch, &Panel::new(Rect::with_size(1, screen_size.1 as i32 - 2, screen_size.0 as i32 - 2, 1), false, Justification::FREE, Option::None));
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+}
@@ -1,17 +1,27 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use std::collections::HashMap;
+use bracket_lib::prelude::RGB;
+use schemars::JsonSchema;
 
-lazy_static! {
-    pub static ref RAW : Mutex<RawMaster> = Mutex::new(RawMaster::empty());
-}
+/// Loaded raws, shared by every state and inserted into each world as a resource. They are never
+/// changed once loaded; reloading builds a new `RawMaster` and replaces the resource, and the game
+/// hands it back to the main menu with `StateResult::Raws` when it ends.
+pub type SharedRaws = Arc<RawMaster>;
 
+#[derive(Default)]
 pub struct RawMaster {
     pub raws : Raws,
-    pub name_to_id : HashMap<String, u32>,
-    pub id_to_name : HashMap<u32, String>,
+    pub key_to_id : HashMap<String, u32>,
+    pub id_to_key : HashMap<u32, String>,
+    /// Position in `raws.items` of each item, by id.
+    id_to_index : HashMap<u32, usize>,
+    /// Where each item was last defined or patched, by id.
+    origins : HashMap<u32, RawOrigin>,
 }
 
 impl RawMaster {
@@ -19,84 +29,445 @@ impl RawMaster {
 
         RawMaster {
             raws : Raws::empty(),
-            name_to_id : HashMap::new(),
-            id_to_name : HashMap::new(),
+            key_to_id : HashMap::new(),
+            id_to_key : HashMap::new(),
+            id_to_index : HashMap::new(),
+            origins : HashMap::new(),
         }
     }
 
-    /// returns the id of an item given its name.
-    pub fn get_item_id (&self, name : String) -> u32 {
-        let result = self.name_to_id.get(&name);
-        match result {
-            Some(id) => {
-                return *id;
-            },
-            None => {
-                error!("No item found with the name \"{}\". Maybe the raw files were edited or the raws have not been loaded?", name);
-                return 0;
-            },
+    /// Returns the id of an item given its key in the raws.
+    pub fn get_item_id (&self, key : &str) -> Option<u32> {
+        return self.key_to_id.get(key).copied();
+    }
+
+    /// Returns the key in the raws of an item given its id.
+    pub fn get_item_key (&self, id : u32) -> Option<&str> {
+        return self.id_to_key.get(&id).map(|key| key.as_str());
+    }
+
+    /// Given an id returns the corresponding item.
+    pub fn get_item (&self, id : u32) -> Option<&ItemRaw> {
+        return self.id_to_index.get(&id).and_then(|idx| self.raws.items.get(*idx));
+    }
+
+    /// Returns where an item was last defined or patched.
+    pub fn get_item_origin (&self, id : u32) -> Option<&RawOrigin> {
+        return self.origins.get(&id);
+    }
+
+    /// Loads every raw file under `raws/`, then those of each mod under `mods/<name>/raws/`. See
+    /// `raw_files` for the load order.
+    pub fn load_raws (&mut self) -> Result<(), RawError> {
+        let files = raw_files(Path::new(RAW_DIR), Path::new(MODS_DIR))?;
+        return self.load_files(&files);
+    }
+
+    /// Loads the given raw files in order. Items are matched by id: a later item replaces an
+    /// earlier one with the same id, or with `"patch" : true` changes only the fields it lists.
+    /// Nothing is loaded if a file can't be read or parsed. If the raws load but fail validation
+    /// they are kept, and every problem found is returned.
+    pub fn load_files (&mut self, files : &[(String, PathBuf)]) -> Result<(), RawError> {
+        let mut problems = Vec::new();
+        let mut entries : Vec<(serde_json::Value, RawOrigin)> = Vec::new();
+        let mut by_key : HashMap<String, usize> = HashMap::new();
+
+        for (source, path) in files.iter() {
+            let file = path.to_string_lossy().to_string();
+            let serialized = match fs::read_to_string(path) {
+                Ok(t) => t,
+                Err(e) => return Err(RawError::Io { file : file, error : e }),
+            };
+            let raw_file : RawFile = match serde_json::from_str(&serialized) {
+                Ok(t) => t,
+                Err(e) => return Err(RawError::Parse { file : file, error : e }),
+            };
+
+            let mut keys_in_file : HashMap<String, usize> = HashMap::new();
+            for (i, mut item) in raw_file.items.into_iter().enumerate() {
+                let origin = RawOrigin {
+                    source : source.clone(),
+                    file : file.clone(),
+                    path : format!("items[{}]", i),
+                };
+                let key = match item.get("id").and_then(|key| key.as_str()) {
+                    Some(key) => key.to_string(),
+                    None => {
+                        problems.push(RawProblem::new(&file, format!("{}.id", origin.path), "missing id"));
+                        continue;
+                    },
+                };
+                match keys_in_file.get(&key) {
+                    Some(first) => {
+                        problems.push(RawProblem::new(&file, format!("{}.id", origin.path), format!("duplicate id \"{}\", first used at items[{}]", key, first)));
+                        continue;
+                    },
+                    None => {
+                        keys_in_file.insert(key.clone(), i);
+                    },
+                }
+
+                let patch = match item.as_object_mut() {
+                    Some(object) => object.remove("patch").and_then(|patch| patch.as_bool()).unwrap_or(false),
+                    None => false,
+                };
+                match by_key.get(&key) {
+                    Some(idx) => {
+                        let entry = &mut entries[*idx];
+                        if patch {
+                            merge_json(&mut entry.0, item);
+                            info!("{} patches item \"{}\" from {}", source, key, entry.1.source);
+                        } else {
+                            entry.0 = item;
+                            info!("{} replaces item \"{}\" from {}", source, key, entry.1.source);
+                        }
+                        entry.1 = origin;
+                    },
+                    None if patch => {
+                        problems.push(RawProblem::new(&file, origin.path.clone(), format!("patches \"{}\", which no earlier file defines", key)));
+                    },
+                    None => {
+                        if source != BASE_SOURCE {
+                            info!("{} adds item \"{}\"", source, key);
+                        }
+                        by_key.insert(key, entries.len());
+                        entries.push((item, origin));
+                    },
+                }
+            }
+        }
+
+        self.raws = Raws::empty();
+        self.key_to_id.clear();
+        self.id_to_key.clear();
+        self.id_to_index.clear();
+        self.origins.clear();
+        let mut origins = Vec::new();
+        for (value, origin) in entries.into_iter() {
+            //point at the item type itself when it is the problem
+            match value.get("item_type").map(|item_type| serde_json::from_value::<ItemType>(item_type.clone())) {
+                Some(Err(e)) => {
+                    problems.push(RawProblem::new(&origin.file, format!("{}.item_type", origin.path), e));
+                    continue;
+                },
+                _ => {},
+            }
+            let item : ItemRaw = match serde_json::from_value(value) {
+                Ok(t) => t,
+                Err(e) => {
+                    problems.push(RawProblem::new(&origin.file, origin.path.clone(), e));
+                    continue;
+                },
+            };
+            let id = item_id(&item.id);
+            match self.id_to_key.get(&id) {
+                Some(other) => {
+                    problems.push(RawProblem::new(&origin.file, format!("{}.id", origin.path), format!("\"{}\" hashes to the same id as \"{}\"; rename one of them", item.id, other)));
+                    continue;
+                },
+                None => {},
+            }
+            //index items for quick look up
+            self.key_to_id.insert(item.id.clone(), id);
+            self.id_to_key.insert(id, item.id.clone());
+            self.id_to_index.insert(id, self.raws.items.len());
+            self.origins.insert(id, origin.clone());
+            self.raws.items.push(item);
+            origins.push(origin);
+        }
+
+        problems.extend(validate(&self.raws, &origins));
+        if !problems.is_empty() {
+            return Err(RawError::Invalid(problems));
         }
+        return Ok(());
     }
 
-    pub fn get_item_name (&self, id : u32) -> String {
-        let result = self.id_to_name.get(&id);
-        match result {
-            Some(name) => {
-                return name.clone();
-            },
-            None => {
-                error!("No item found with the id \"{}\". Maybe the raw files were edited or the raws have not been loaded?", id);
-                return "Diamond".to_string();
-            },
+}
+
+const RAW_DIR : &str = "raws";
+const MODS_DIR : &str = "mods";
+/// Source name of the game's own raws.
+pub const BASE_SOURCE : &str = "base";
+/// Effects the game knows how to apply.
+pub const EFFECTS : [&str; 2] = ["heal", "poison"];
+
+/// Id of an item, derived from its key in the raws so that it doesn't change when items are added,
+/// removed, renamed or loaded in a different order.
+pub fn item_id (key : &str) -> u32 {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::default();
+    hasher.input(key.as_bytes());
+    let hash = hasher.result();
+    return u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+}
+
+/// Lists the raw files to load, in load order: every JSON file under `raw_dir`, then every JSON
+/// file under `<mod>/raws` for each directory in `mods_dir`. Mods load in alphabetical order, as do
+/// files within a directory, so later mods override earlier ones. Each file is paired with the name
+/// of its source, `base` or the mod's directory name. A missing mods directory means no mods.
+pub fn raw_files (raw_dir : &Path, mods_dir : &Path) -> Result<Vec<(String, PathBuf)>, RawError> {
+    let mut files : Vec<(String, PathBuf)> = json_files(raw_dir)?.into_iter().map(|file| (BASE_SOURCE.to_string(), file)).collect();
+
+    if !mods_dir.is_dir() {
+        return Ok(files);
+    }
+    let mut mods = sorted_entries(mods_dir)?;
+    mods.retain(|path| path.join(RAW_DIR).is_dir());
+    for mod_dir in mods.iter() {
+        let name = mod_dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        info!("Loading mod \"{}\"", name);
+        for file in json_files(&mod_dir.join(RAW_DIR))? {
+            files.push((name.clone(), file));
         }
     }
+    return Ok(files);
+}
 
-    /// Given an id returns the corresponding item.
-    pub fn get_item (&self, id : u32) -> ItemRaw {
-        let item = self.raws.items[id as usize].clone();
-        return item;
-    }
-    /// loads the raws into memory
-    pub fn load_raws (&mut self) {
-
-        // attempt to read raw files; if the raw file isn't found, log an error.
-        let file_result = fs::read_to_string("raws/items.json");
-        let mut serialized = "".to_string();
-        match file_result {
-            Ok(t) => {
-                serialized = t;
-            },
-            Err(e) => {
-                error!("ERROR READING RAW FILE : {}", e);
+/// Milliseconds between checks of the raw files for changes.
+const WATCH_INTERVAL : f32 = 1000.0;
+
+/// Notices when raw files are added, removed or changed, so they can be reloaded while the game runs.
+pub struct RawWatcher {
+    /// Each raw file and when it was last modified, as of the last check.
+    modified : Vec<(PathBuf, Option<SystemTime>)>,
+    since_last_check : f32,
+}
+
+impl Default for RawWatcher {
+    fn default () -> Self {
+        return RawWatcher::new();
+    }
+}
+
+impl RawWatcher {
+    pub fn new () -> Self {
+        RawWatcher {
+            modified : RawWatcher::snapshot(),
+            since_last_check : 0.0,
+        }
+    }
+
+    fn snapshot () -> Vec<(PathBuf, Option<SystemTime>)> {
+        let files = match raw_files(Path::new(RAW_DIR), Path::new(MODS_DIR)) {
+            Ok(files) => files,
+            Err(_e) => return Vec::new(),
+        };
+        return files.into_iter().map(|(_source, path)| {
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            (path, modified)
+        }).collect();
+    }
+
+    /// Returns true if the raw files have changed since the last time this returned true. Only
+    /// looks at the files every `WATCH_INTERVAL` milliseconds.
+    pub fn poll (&mut self, delta : f32) -> bool {
+        self.since_last_check += delta;
+        if self.since_last_check < WATCH_INTERVAL {
+            return false;
+        }
+        self.since_last_check = 0.0;
+
+        let modified = RawWatcher::snapshot();
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        return true;
+    }
+}
+
+/// Every JSON file under a directory, in alphabetical order, searching subdirectories in place.
+fn json_files (dir : &Path) -> Result<Vec<PathBuf>, RawError> {
+    let mut files = Vec::new();
+    for path in sorted_entries(dir)? {
+        if path.is_dir() {
+            files.extend(json_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "json") {
+            files.push(path);
+        }
+    }
+    return Ok(files);
+}
+
+fn sorted_entries (dir : &Path) -> Result<Vec<PathBuf>, RawError> {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(t) => t,
+        Err(e) => return Err(RawError::Io { file : dir.to_string_lossy().to_string(), error : e }),
+    };
+    let mut paths : Vec<PathBuf> = read_dir.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+    paths.sort();
+    return Ok(paths);
+}
+
+/// Copies the fields of `patch` over `base`. Objects merge field by field; anything else is replaced.
+fn merge_json (base : &mut serde_json::Value, patch : serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch.into_iter() {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, patch) => *base = patch,
+    }
+}
+
+/// Where an item's raw came from.
+#[derive(Debug, Clone)]
+pub struct RawOrigin {
+    /// `base` or the name of the mod.
+    pub source : String,
+    pub file : String,
+    /// Location of the item in the file, e.g. `items[3]`.
+    pub path : String,
+}
+
+/// Why the raws couldn't be loaded.
+#[derive(Debug)]
+pub enum RawError {
+    /// A raw file couldn't be read.
+    Io { file : String, error : std::io::Error },
+    /// A raw file isn't valid JSON or doesn't have the expected shape.
+    Parse { file : String, error : serde_json::Error },
+    /// The raws loaded but contain bad values.
+    Invalid(Vec<RawProblem>),
+}
+
+impl std::fmt::Display for RawError {
+    fn fmt (&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RawError::Io { file, error } => write!(f, "Unable to read raw file {} : {}", file, error),
+            RawError::Parse { file, error } => write!(f, "Unable to parse raw file {} : {}", file, error),
+            RawError::Invalid(problems) => write!(f, "Found {} problem(s) in the raw files", problems.len()),
+        }
+    }
+}
+
+/// A bad value in a raw file.
+#[derive(Debug, Clone)]
+pub struct RawProblem {
+    pub file : String,
+    /// Location of the value in the file, e.g. `items[3].renderable.fg`.
+    pub path : String,
+    pub message : String,
+}
+
+impl RawProblem {
+    fn new<S : ToString> (file : &str, path : String, message : S) -> Self {
+        RawProblem {
+            file : file.to_string(),
+            path : path,
+            message : message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for RawProblem {
+    fn fmt (&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(f, "{} at {}: {}", self.file, self.path, self.message);
+    }
+}
+
+/// Checks every item and returns all the problems found. `origins` gives where each item came from.
+pub fn validate (raws : &Raws, origins : &[RawOrigin]) -> Vec<RawProblem> {
+    let mut problems = Vec::new();
+
+    for (item, origin) in raws.items.iter().zip(origins.iter()) {
+        let file = origin.file.as_str();
+        let path = origin.path.as_str();
+
+        match &item.renderable {
+            Some(renderable) => {
+                for (field, color) in [("fg", &renderable.fg), ("bg", &renderable.bg)].iter() {
+                    if RGB::from_hex(color).is_err() {
+                        problems.push(RawProblem::new(file, format!("{}.renderable.{}", path, field), format!("\"{}\" is not a hex color such as #ff9a5c", color)));
+                    }
+                }
+                if renderable.character_code < 0 || renderable.character_code > 255 {
+                    problems.push(RawProblem::new(file, format!("{}.renderable.character_code", path), format!("{} is outside 0 to 255", renderable.character_code)));
+                }
             },
+            None => problems.push(RawProblem::new(file, format!("{}.renderable", path), "missing renderable")),
         }
 
-        // attempt to deserialize the data from the raw files, if not log error
-        let deserialization_result : Result<Raws, serde_json::Error> = serde_json::from_str(&serialized);
-        match deserialization_result {
-            Ok(t) => {
-                self.raws.items = t.items.clone();
+        let effects = [
+            ("potion", item.potion.as_ref().and_then(|potion| potion.effects.as_ref())),
+            ("melee_weapon", item.melee_weapon.as_ref().and_then(|weapon| weapon.effects.as_ref())),
+            ("consumable", item.consumable.as_ref().and_then(|consumable| consumable.effects.as_ref())),
+        ];
+        for (section, effects) in effects.iter() {
+            for (j, effect) in effects.iter().flat_map(|effects| effects.iter()).enumerate() {
+                if !EFFECTS.contains(&effect.as_str()) {
+                    problems.push(RawProblem::new(file, format!("{}.{}.effects[{}]", path, section, j), format!("unknown effect \"{}\"", effect)));
+                }
+            }
+        }
+
+        let mut colors = Vec::new();
+        match &item.light {
+            Some(light) => colors.push(("light.color", &light.color)),
+            None => {},
+        }
+        match &item.color_lerp {
+            Some(color_lerp) => {
+                colors.push(("color_lerp.color_a", &color_lerp.color_a));
+                colors.push(("color_lerp.color_b", &color_lerp.color_b));
+                if color_lerp.rate <= 0.0 {
+                    problems.push(RawProblem::new(file, format!("{}.color_lerp.rate", path), "rate must be above 0"));
+                }
             },
-            Err(e) => {
-                error!("ERROR LOADING RAW FILE : {}", e);
+            None => {},
+        }
+        for (field, color) in colors.iter() {
+            if RGB::from_hex(color).is_err() {
+                problems.push(RawProblem::new(file, format!("{}.{}", path, field), format!("\"{}\" is not a hex color such as #ff9a5c", color)));
+            }
+        }
+
+        match &item.cycle_animation {
+            Some(animation) => {
+                if animation.rate <= 0.0 {
+                    problems.push(RawProblem::new(file, format!("{}.cycle_animation.rate", path), "rate must be above 0"));
+                }
+                if animation.frames.is_empty() {
+                    problems.push(RawProblem::new(file, format!("{}.cycle_animation.frames", path), "needs at least one frame"));
+                }
+                for (j, frame) in animation.frames.iter().enumerate() {
+                    if *frame < 0 || *frame > 255 {
+                        problems.push(RawProblem::new(file, format!("{}.cycle_animation.frames[{}]", path, j), format!("{} is outside 0 to 255", frame)));
+                    }
+                }
             },
+            None => {},
         }
 
-        //index items for quick look up
-        let length = self.raws.items.len();
-        for i in 0..length {
-            let name = self.raws.items[i].name.clone();
-            self.name_to_id.insert(name.clone(), i as u32);
-            self.id_to_name.insert(i as u32, name.clone());
+        match &item.currency {
+            Some(currency) if currency.min > currency.max => {
+                problems.push(RawProblem::new(file, format!("{}.currency", path), format!("min {} is above max {}", currency.min, currency.max)));
+            },
+            _ => {},
         }
     }
-
+    return problems;
 }
 
+/// A raw file before its items are merged with those of other files.
+#[derive(Deserialize)]
+struct RawFile {
+    #[serde(default)]
+    items : Vec<serde_json::Value>,
+}
 
-
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Default, JsonSchema)]
 pub struct Raws {
+    /// Items to add. An item with the id of one from an earlier file replaces it, or changes only
+    /// the fields given if it has `"patch" : true`. A file without items is fine.
+    #[serde(default)]
     pub items : Vec<ItemRaw>,
 }
 
@@ -108,32 +479,357 @@ impl Raws {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ItemRaw {
-    pub item_type : String,
+    pub item_type : ItemType,
+    /// Unique key identifying the item to later files and to the string tables, e.g. `health_potion`.
+    pub id : String,
+    /// Name shown to the player when there is no `item.<id>` translation.
     pub name : String,
     pub weight : f32,
+    /// Chance of the item being chosen, from 0 to 1.
     pub rarity : f32,
+    /// Worth in gold; for currency, the worth of one coin.
     pub value : f32,
     pub renderable : Option<RenderableRaw>,
     pub potion : Option<PotionRaw>,
     pub melee_weapon : Option<MeleeWeaponRaw>,
+    pub light : Option<LightRaw>,
+    pub color_lerp : Option<ColorLerpRaw>,
+    pub cycle_animation : Option<CycleAnimationRaw>,
+    pub currency : Option<CurrencyRaw>,
+    pub equipable : Option<EquipableRaw>,
+    pub consumable : Option<ConsumableRaw>,
+}
+
+/// Kind of item, which decides how it is built and what picking it up does. Written in the raws in
+/// snake case, e.g. `melee_weapon`; any other value fails to load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemType {
+    Potion,
+    Currency,
+    Treasure,
+    MeleeWeapon,
+    Armor,
+    Food,
+    Scroll,
+    LightSource,
+    Container,
+    Key,
+}
+
+impl ItemType {
+    /// Name used in the raws.
+    pub fn key (&self) -> &'static str {
+        match self {
+            ItemType::Potion => "potion",
+            ItemType::Currency => "currency",
+            ItemType::Treasure => "treasure",
+            ItemType::MeleeWeapon => "melee_weapon",
+            ItemType::Armor => "armor",
+            ItemType::Food => "food",
+            ItemType::Scroll => "scroll",
+            ItemType::LightSource => "light_source",
+            ItemType::Container => "container",
+            ItemType::Key => "key",
+        }
+    }
+
+    /// Name shown to the player when there is no translation.
+    pub fn name (&self) -> &'static str {
+        match self {
+            ItemType::Potion => "potion",
+            ItemType::Currency => "currency",
+            ItemType::Treasure => "treasure",
+            ItemType::MeleeWeapon => "melee weapon",
+            ItemType::Armor => "armor",
+            ItemType::Food => "food",
+            ItemType::Scroll => "scroll",
+            ItemType::LightSource => "light source",
+            ItemType::Container => "container",
+            ItemType::Key => "key",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl std::fmt::Display for ItemType {
+    fn fmt (&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        return write!(f, "{}", self.name());
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct RenderableRaw {
+    /// Glyph in the font, from 0 to 255.
     pub character_code : i32,
+    /// Named sprite to use instead of the character code.
+    pub sprite : Option<String>,
+    /// Hex color such as #ff9a5c.
     pub fg : String,
+    /// Hex color such as #000000.
     pub bg : String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct MeleeWeaponRaw {
     pub base_damage : u32,
     pub effects : Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Light given off by the item while it lies on the map.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct LightRaw {
+    pub radius : u32,
+    pub intensity : f32,
+    pub color : String,
+}
+
+/// Makes the item's foreground color pulse between two colors.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ColorLerpRaw {
+    pub color_a : String,
+    pub color_b : String,
+    /// Milliseconds to go from one color to the other.
+    pub rate : f32,
+    /// Milliseconds into the first pulse the animation starts at.
+    #[serde(default)]
+    pub offset : f32,
+}
+
+/// Makes the item's glyph cycle through a list of character codes.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CycleAnimationRaw {
+    /// Milliseconds each frame is shown for.
+    pub rate : f32,
+    pub frames : Vec<i32>,
+}
+
+/// How many of a currency item are in one pile, picked at random when the pile is built.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct CurrencyRaw {
+    pub min : u32,
+    pub max : u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct EquipableRaw {
+    pub slot : EquipSlot,
+}
+
+/// Where an equipable item is worn or held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipSlot {
+    Weapon,
+    Body,
+    Light,
+}
+
+impl EquipSlot {
+    /// Name used in the raws.
+    pub fn key (&self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "weapon",
+            EquipSlot::Body => "body",
+            EquipSlot::Light => "light",
+        }
+    }
+
+    /// Name shown to the player when there is no translation.
+    pub fn name (&self) -> &'static str {
+        match self {
+            EquipSlot::Weapon => "weapon",
+            EquipSlot::Body => "body",
+            EquipSlot::Light => "light",
+        }
+    }
+}
+
+/// An item that is used up when used.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ConsumableRaw {
+    pub effects : Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct PotionRaw {
     pub effects : Option<Vec<String>>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Directory under the system temp dir that is removed again when dropped.
+    struct TempDir (PathBuf);
+
+    impl TempDir {
+        fn new (name : &str) -> Self {
+            let path = std::env::temp_dir().join(format!("polymorph-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            return TempDir(path);
+        }
+
+        /// Writes a file, creating the directories leading to it.
+        fn write (&self, file : &str, contents : &str) -> PathBuf {
+            let path = self.0.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            return path;
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop (&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn every_problem_in_a_file_is_reported () {
+        let dir = TempDir::new("validation");
+        let file = dir.write("items.json", r##"{
+            "items" : [
+                { "item_type" : "treasure", "id" : "lamp", "name" : "Lamp", "weight" : 1.0, "rarity" : 0.1, "value" : 1.0,
+                  "renderable" : { "character_code" : 300, "fg" : "#zz0000", "bg" : "#000000" } },
+                { "item_type" : "treasure", "id" : "lamp", "name" : "Lamp", "weight" : 1.0, "rarity" : 0.1, "value" : 1.0,
+                  "renderable" : { "character_code" : 1, "fg" : "#ffffff", "bg" : "#000000" } },
+                { "item_type" : "treasure", "id" : "rock", "name" : "Rock", "weight" : 1.0, "rarity" : 0.1, "value" : 1.0 },
+                { "item_type" : "gizmo", "id" : "orb", "name" : "Orb", "weight" : 1.0, "rarity" : 0.1, "value" : 1.0,
+                  "renderable" : { "character_code" : 1, "fg" : "#ffffff", "bg" : "#000000" } },
+                { "item_type" : "potion", "id" : "tonic", "name" : "Tonic", "weight" : 1.0, "rarity" : 0.1, "value" : 1.0,
+                  "renderable" : { "character_code" : 1, "fg" : "#ffffff", "bg" : "#000000" },
+                  "potion" : { "effects" : [ "heal", "explode" ] } }
+            ]
+        }"##);
+
+        let mut raws = RawMaster::empty();
+        let problems = match raws.load_files(&[(BASE_SOURCE.to_string(), file.clone())]) {
+            Err(RawError::Invalid(problems)) => problems,
+            other => panic!("expected validation problems, got {:?}", other),
+        };
+
+        let file_name = file.to_string_lossy().to_string();
+        assert!(problems.iter().all(|problem| problem.file == file_name), "{:?}", problems);
+        let found : Vec<(&str, &str)> = problems.iter().map(|problem| (problem.path.as_str(), problem.message.as_str())).collect();
+        let expected = [
+            ("items[1].id", "duplicate id \"lamp\", first used at items[0]"),
+            ("items[3].item_type", "unknown variant `gizmo`"),
+            ("items[0].renderable.fg", "\"#zz0000\" is not a hex color such as #ff9a5c"),
+            ("items[0].renderable.character_code", "300 is outside 0 to 255"),
+            ("items[2].renderable", "missing renderable"),
+            ("items[4].potion.effects[1]", "unknown effect \"explode\""),
+        ];
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for ((path, message), (expected_path, expected_message)) in found.iter().zip(expected.iter()) {
+            assert_eq!(path, expected_path);
+            assert!(message.starts_with(expected_message), "{} at {}", message, path);
+        }
+
+        //the good items are still loaded
+        assert!(raws.get_item_id("lamp").is_some());
+        assert!(raws.get_item_id("tonic").is_some());
+        assert!(raws.get_item_id("orb").is_none());
+    }
+
+    #[test]
+    fn item_types_are_written_in_snake_case () {
+        let types = [
+            ItemType::Potion, ItemType::Currency, ItemType::Treasure, ItemType::MeleeWeapon, ItemType::Armor,
+            ItemType::Food, ItemType::Scroll, ItemType::LightSource, ItemType::Container, ItemType::Key,
+        ];
+        for item_type in types.iter() {
+            let written = format!("\"{}\"", item_type.key());
+            assert_eq!(serde_json::from_str::<ItemType>(&written).unwrap(), *item_type);
+            assert_eq!(serde_json::to_string(item_type).unwrap(), written);
+        }
+
+        for unknown in ["\"MeleeWeapon\"", "\"melee weapon\"", "\"Potion\"", "\"gizmo\"", "\"\""].iter() {
+            assert!(serde_json::from_str::<ItemType>(unknown).is_err(), "{} was accepted", unknown);
+        }
+    }
+
+    #[test]
+    fn schema_allows_files_without_items () {
+        let schema = schemars::schema_for!(Raws);
+        assert!(!schema.schema.object.unwrap().required.contains("items"));
+        assert_eq!(serde_json::from_str::<Raws>("{}").unwrap().items.len(), 0);
+    }
+
+    /// Base raws and two mods, written so that the mods sort differently from how they were created.
+    fn modded_tree (dir : &TempDir) {
+        dir.write("raws/items.json", r##"{
+            "items" : [
+                { "item_type" : "currency", "id" : "gold", "name" : "Gold", "weight" : 0.0, "rarity" : 0.25, "value" : 1.0,
+                  "renderable" : { "character_code" : 42, "fg" : "#eeca00", "bg" : "#000000" },
+                  "currency" : { "min" : 1, "max" : 10 } },
+                { "item_type" : "melee_weapon", "id" : "sword", "name" : "Sword", "weight" : 3.0, "rarity" : 0.1, "value" : 10.0,
+                  "renderable" : { "character_code" : 47, "fg" : "#ffffff", "bg" : "#000000" } }
+            ]
+        }"##);
+        dir.write("raws/more/extra.json", r##"{ "items" : [] }"##);
+        dir.write("mods/b/raws/items.json", r##"{
+            "items" : [
+                { "item_type" : "melee_weapon", "id" : "sword", "name" : "Broadsword", "weight" : 3.0, "rarity" : 0.1, "value" : 30.0,
+                  "renderable" : { "character_code" : 47, "fg" : "#ff0000", "bg" : "#000000" } }
+            ]
+        }"##);
+        dir.write("mods/a/raws/items.json", r##"{
+            "items" : [
+                { "item_type" : "melee_weapon", "id" : "sword", "name" : "Sword", "weight" : 3.0, "rarity" : 0.1, "value" : 20.0,
+                  "renderable" : { "character_code" : 47, "fg" : "#00ff00", "bg" : "#000000" } },
+                { "id" : "gold", "patch" : true, "value" : 2.0, "currency" : { "max" : 20 } }
+            ]
+        }"##);
+        //not a mod without a raws directory
+        dir.write("mods/c/readme.txt", "nothing to load");
+    }
+
+    #[test]
+    fn mods_load_after_the_base_raws_in_alphabetical_order () {
+        let dir = TempDir::new("load-order");
+        modded_tree(&dir);
+
+        let files = raw_files(&dir.0.join("raws"), &dir.0.join("mods")).unwrap();
+        let found : Vec<(&str, PathBuf)> = files.iter().map(|(source, path)| (source.as_str(), path.strip_prefix(&dir.0).unwrap().to_path_buf())).collect();
+        assert_eq!(found, vec![
+            ("base", PathBuf::from("raws/items.json")),
+            ("base", PathBuf::from("raws/more/extra.json")),
+            ("a", PathBuf::from("mods/a/raws/items.json")),
+            ("b", PathBuf::from("mods/b/raws/items.json")),
+        ]);
+    }
+
+    #[test]
+    fn later_mods_override_and_patch_items () {
+        let dir = TempDir::new("overrides");
+        modded_tree(&dir);
+
+        let mut base = RawMaster::empty();
+        base.load_files(&raw_files(&dir.0.join("raws"), &dir.0.join("no-mods")).unwrap()).unwrap();
+        let mut modded = RawMaster::empty();
+        modded.load_files(&raw_files(&dir.0.join("raws"), &dir.0.join("mods")).unwrap()).unwrap();
+
+        //the last mod to define an item wins
+        let sword_id = modded.get_item_id("sword").unwrap();
+        let sword = modded.get_item(sword_id).unwrap();
+        assert_eq!(sword.value, 30.0);
+        assert_eq!(sword.name, "Broadsword");
+        assert_eq!(sword.renderable.as_ref().unwrap().fg, "#ff0000");
+        assert_eq!(modded.get_item_origin(sword_id).unwrap().source, "b");
+
+        //a patch changes only the fields it lists
+        let gold_id = modded.get_item_id("gold").unwrap();
+        let gold = modded.get_item(gold_id).unwrap();
+        assert_eq!(gold.value, 2.0);
+        assert_eq!(gold.currency.as_ref().map(|currency| (currency.min, currency.max)), Option::from((1, 20)));
+        assert_eq!(gold.renderable.as_ref().unwrap().fg, "#eeca00");
+        assert_eq!(modded.get_item_origin(gold_id).unwrap().source, "a");
+
+        //ids don't depend on what else is loaded
+        assert_eq!(base.get_item_id("gold"), Option::from(gold_id));
+        assert_eq!(base.get_item_id("sword"), Option::from(sword_id));
+        assert_eq!(base.get_item(gold_id).unwrap().value, 1.0);
+    }
+}
@@ -0,0 +1,599 @@
+use bracket_lib::prelude::{BTerm, VirtualKeyCode, DrawBatch, Rect, RGB, render_draw_buffer};
+use object_pool::Reusable;
+
+use crate::state::{StateAction, State, CurrentInput, DeltaTime, StateResult};
+use crate::components::gui::{Panel, Justification};
+use crate::config::{Settings, SkyPhase, MIN_CONSOLE_SIZE, create_new_config_file};
+use crate::widgets::{Label, ListView, Tabs, TextInput, Modal, ModalResult, draw_window};
+use crate::input::{Command, KeyChord, KeyBindings, ALL_COMMANDS, PRESETS};
+use crate::locale::{Strings, SharedStrings, available_languages};
+
+type Getter<T> = Box<dyn Fn(&Settings) -> T>;
+type Setter<T> = Box<dyn Fn(&mut Settings, T)>;
+/// Returns an error message if the text isn't a valid value.
+type TextSetter = Box<dyn Fn(&mut Settings, String) -> Result<(), String>>;
+
+/// How a setting is shown and edited.
+enum FieldValue {
+    Toggle {
+        get : Getter<bool>,
+        set : Setter<bool>,
+    },
+    Text {
+        get : Getter<String>,
+        set : TextSetter,
+    },
+    Number {
+        get : Getter<f32>,
+        set : Setter<f32>,
+        step : f32,
+        min : f32,
+        max : f32,
+    },
+    Choice {
+        options : Vec<String>,
+        get : Getter<String>,
+        set : Setter<String>,
+    },
+    /// Keys bound to a command. Edited by pressing the new key.
+    KeyBinding {
+        command : Command,
+    },
+}
+
+/// A single editable setting.
+struct SettingField {
+    /// String table key of the tab the setting is under.
+    section : &'static str,
+    label : String,
+    /// The game has to be restarted before a change takes effect.
+    restart : bool,
+    value : FieldValue,
+}
+
+impl SettingField {
+    fn new<S : ToString> (section : &'static str, label : S, restart : bool, value : FieldValue) -> Self {
+        SettingField {
+            section : section,
+            label : label.to_string(),
+            restart : restart,
+            value : value,
+        }
+    }
+
+    fn display_value (&self, settings : &Settings, strings : &Strings) -> String {
+        match &self.value {
+            FieldValue::Toggle { get, .. } => if get(settings) { strings.get("settings.on").to_string() } else { strings.get("settings.off").to_string() },
+            FieldValue::Text { get, .. } => get(settings),
+            FieldValue::Number { get, step, .. } => {
+                if step.fract() == 0.0 {
+                    format!("{}", get(settings))
+                } else {
+                    format!("{:.2}", get(settings))
+                }
+            },
+            FieldValue::Choice { get, .. } => get(settings),
+            FieldValue::KeyBinding { command } => {
+                match settings.keybindings.bindings.get(command.name()) {
+                    Some(chords) => chords.join(", "),
+                    None => strings.get("settings.from_preset").to_string(),
+                }
+            },
+        }
+    }
+
+    /// Changes the setting by one step in the given direction. Text settings are edited separately.
+    fn adjust (&self, settings : &mut Settings, direction : i32) {
+        match &self.value {
+            FieldValue::Toggle { get, set } => {
+                let value = get(settings);
+                set(settings, !value);
+            },
+            FieldValue::Number { get, set, step, min, max } => {
+                let value = (get(settings) + step * direction as f32).max(*min).min(*max);
+                // keep decimals from drifting
+                let value = (value / step).round() * step;
+                set(settings, value);
+            },
+            FieldValue::Choice { options, get, set } => {
+                if options.is_empty() {
+                    return;
+                }
+                let current = get(settings);
+                let idx = options.iter().position(|option| *option == current).unwrap_or(0) as i32;
+                let next = (idx + direction).rem_euclid(options.len() as i32) as usize;
+                set(settings, options[next].clone());
+            },
+            FieldValue::Text { .. } | FieldValue::KeyBinding { .. } => {},
+        }
+    }
+}
+
+fn validate_color (color : &str, strings : &Strings) -> Result<(), String> {
+    match RGB::from_hex(color) {
+        Ok(_v) => return Ok(()),
+        Err(_e) => return Err(strings.format("settings.bad_color", &[&color])),
+    }
+}
+
+/// Fields for one phase of the day/night schedule.
+fn sky_phase_fields (fields : &mut Vec<SettingField>, strings : &SharedStrings, name_key : &str, phase : fn(&Settings) -> &SkyPhase, phase_mut : fn(&mut Settings) -> &mut SkyPhase) {
+    let name = strings.get(name_key);
+    let color_strings = strings.clone();
+    fields.push(SettingField::new(DAYLIGHT, strings.format("settings.color", &[&name]), false, FieldValue::Text {
+        get : Box::new(move |s| phase(s).color.clone()),
+        set : Box::new(move |s, v| { validate_color(&v, &color_strings)?; phase_mut(s).color = v; Ok(()) }),
+    }));
+    fields.push(SettingField::new(DAYLIGHT, strings.format("settings.intensity", &[&name]), false, FieldValue::Number {
+        get : Box::new(move |s| phase(s).intensity),
+        set : Box::new(move |s, v| phase_mut(s).intensity = v),
+        step : 0.05, min : 0.0, max : 1.0,
+    }));
+}
+
+const DEVELOPMENT : &str = "settings.section.development";
+const GRAPHICAL : &str = "settings.section.graphical";
+const POST_PROCESSING : &str = "settings.section.post_processing";
+const DAYLIGHT : &str = "settings.section.daylight";
+const OTHER : &str = "settings.section.other";
+const KEY_BINDINGS : &str = "settings.section.key_bindings";
+
+/// Every setting the screen can edit, in display order.
+fn build_fields (settings : &Settings, strings : &SharedStrings) -> Vec<SettingField> {
+    let mut fields = Vec::new();
+
+    fields.push(SettingField::new(DEVELOPMENT, strings.get("settings.debug_logging"), true, FieldValue::Toggle {
+        get : Box::new(|s| s.development.debug),
+        set : Box::new(|s, v| s.development.debug = v),
+    }));
+
+    let tileset_names : Vec<String> = settings.graphical.tilesets.iter().map(|tileset| tileset.name.clone()).collect();
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.fullscreen"), true, FieldValue::Toggle {
+        get : Box::new(|s| s.graphical.fullscreen),
+        set : Box::new(|s, v| s.graphical.fullscreen = v),
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.vsync"), true, FieldValue::Toggle {
+        get : Box::new(|s| s.graphical.vsync),
+        set : Box::new(|s, v| s.graphical.vsync = v),
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.tileset"), true, FieldValue::Choice {
+        options : tileset_names.clone(),
+        get : Box::new(|s| s.graphical.tileset.clone()),
+        set : Box::new(|s, v| s.graphical.tileset = v),
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.text_font"), true, FieldValue::Choice {
+        options : tileset_names,
+        get : Box::new(|s| s.graphical.text_font.clone()),
+        set : Box::new(|s, v| s.graphical.text_font = v),
+    }));
+
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.auto_console_size"), false, FieldValue::Toggle {
+        get : Box::new(|s| s.graphical.console.auto),
+        set : Box::new(|s, v| s.graphical.console.auto = v),
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.console_width"), false, FieldValue::Number {
+        get : Box::new(|s| s.graphical.console.width as f32),
+        set : Box::new(|s, v| s.graphical.console.width = v as u32),
+        step : 1.0, min : MIN_CONSOLE_SIZE.0 as f32, max : 1000.0,
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.console_height"), false, FieldValue::Number {
+        get : Box::new(|s| s.graphical.console.height as f32),
+        set : Box::new(|s, v| s.graphical.console.height = v as u32),
+        step : 1.0, min : MIN_CONSOLE_SIZE.1 as f32, max : 1000.0,
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.tile_width"), true, FieldValue::Number {
+        get : Box::new(|s| s.graphical.console.tile_width as f32),
+        set : Box::new(|s, v| s.graphical.console.tile_width = v as u32),
+        step : 1.0, min : 0.0, max : 128.0,
+    }));
+    fields.push(SettingField::new(GRAPHICAL, strings.get("settings.tile_height"), true, FieldValue::Number {
+        get : Box::new(|s| s.graphical.console.tile_height as f32),
+        set : Box::new(|s, v| s.graphical.console.tile_height = v as u32),
+        step : 1.0, min : 0.0, max : 128.0,
+    }));
+
+    fields.push(SettingField::new(POST_PROCESSING, strings.get("settings.scan_lines"), false, FieldValue::Toggle {
+        get : Box::new(|s| s.graphical.post_processing.scan_lines),
+        set : Box::new(|s, v| s.graphical.post_processing.scan_lines = v),
+    }));
+    fields.push(SettingField::new(POST_PROCESSING, strings.get("settings.screen_burn"), false, FieldValue::Toggle {
+        get : Box::new(|s| s.graphical.post_processing.screen_burn),
+        set : Box::new(|s, v| s.graphical.post_processing.screen_burn = v),
+    }));
+
+    fields.push(SettingField::new(DAYLIGHT, strings.get("settings.day_length"), true, FieldValue::Number {
+        get : Box::new(|s| s.graphical.daylight.day_length as f32),
+        set : Box::new(|s, v| s.graphical.daylight.day_length = v as u32),
+        step : 50.0, min : 50.0, max : 100000.0,
+    }));
+    fields.push(SettingField::new(DAYLIGHT, strings.get("settings.start_time"), true, FieldValue::Number {
+        get : Box::new(|s| s.graphical.daylight.start_time),
+        set : Box::new(|s, v| s.graphical.daylight.start_time = v),
+        step : 0.05, min : 0.0, max : 1.0,
+    }));
+    sky_phase_fields(&mut fields, strings, "settings.phase.dawn", |s| &s.graphical.daylight.dawn, |s| &mut s.graphical.daylight.dawn);
+    sky_phase_fields(&mut fields, strings, "settings.phase.day", |s| &s.graphical.daylight.day, |s| &mut s.graphical.daylight.day);
+    sky_phase_fields(&mut fields, strings, "settings.phase.dusk", |s| &s.graphical.daylight.dusk, |s| &mut s.graphical.daylight.dusk);
+    sky_phase_fields(&mut fields, strings, "settings.phase.night", |s| &s.graphical.daylight.night, |s| &mut s.graphical.daylight.night);
+    for (idx, lighting) in settings.graphical.daylight.overrides.iter().enumerate() {
+        let name = strings.map_type_name(lighting.map_type);
+        let color_strings = strings.clone();
+        fields.push(SettingField::new(DAYLIGHT, strings.format("settings.color", &[&name]), false, FieldValue::Text {
+            get : Box::new(move |s| s.graphical.daylight.overrides[idx].color.clone()),
+            set : Box::new(move |s, v| { validate_color(&v, &color_strings)?; s.graphical.daylight.overrides[idx].color = v; Ok(()) }),
+        }));
+        fields.push(SettingField::new(DAYLIGHT, strings.format("settings.intensity", &[&name]), false, FieldValue::Number {
+            get : Box::new(move |s| s.graphical.daylight.overrides[idx].intensity),
+            set : Box::new(move |s, v| s.graphical.daylight.overrides[idx].intensity = v),
+            step : 0.05, min : 0.0, max : 1.0,
+        }));
+    }
+
+    fields.push(SettingField::new(OTHER, strings.get("settings.screenshot_location"), false, FieldValue::Text {
+        get : Box::new(|s| s.other.screenshot_location.clone()),
+        set : Box::new(|s, v| { s.other.screenshot_location = v; Ok(()) }),
+    }));
+    fields.push(SettingField::new(OTHER, strings.get("settings.auto_explore_pickup"), true, FieldValue::Toggle {
+        get : Box::new(|s| s.other.auto_explore_pickup),
+        set : Box::new(|s, v| s.other.auto_explore_pickup = v),
+    }));
+    fields.push(SettingField::new(OTHER, strings.get("settings.language"), true, FieldValue::Choice {
+        options : available_languages(),
+        get : Box::new(|s| s.other.language.clone()),
+        set : Box::new(|s, v| s.other.language = v),
+    }));
+
+    fields.push(SettingField::new(KEY_BINDINGS, strings.get("settings.preset"), false, FieldValue::Choice {
+        options : PRESETS.iter().map(|preset| preset.to_string()).collect(),
+        get : Box::new(|s| s.keybindings.preset.clone()),
+        set : Box::new(|s, v| s.keybindings.preset = v),
+    }));
+    for command in ALL_COMMANDS.iter() {
+        fields.push(SettingField::new(KEY_BINDINGS, strings.get(&format!("command.{}", command.name())), false, FieldValue::KeyBinding { command : *command }));
+    }
+
+    return fields;
+}
+
+/// Edits the settings and saves them to config.toml.
+pub struct SettingsState {
+    settings : Settings,
+    /// Settings as they were opened or last saved, put back when changes are discarded.
+    saved : Settings,
+    fields : Vec<SettingField>,
+    /// String table key of each tab, in tab order.
+    sections : Vec<&'static str>,
+    tabs : Tabs,
+    list : ListView,
+    /// Indices into `fields` of the settings under the active tab, in list order.
+    shown : Vec<usize>,
+    /// Set while a text setting is being typed in.
+    editing : Option<TextInput>,
+    /// Set while waiting for the key to bind to a command.
+    capturing : Option<Command>,
+    /// Bindings as they'd be loaded from the current settings, used to show what each key ends up doing.
+    key_bindings : KeyBindings,
+    unsaved : bool,
+    confirm_exit : Option<Modal>,
+    status : String,
+    strings : SharedStrings,
+    draw_batch : Reusable<'static, DrawBatch>,
+}
+
+impl SettingsState {
+    pub fn new (settings : Settings, strings : SharedStrings) -> Self {
+        let fields = build_fields(&settings, &strings);
+        let mut sections : Vec<&'static str> = Vec::new();
+        for field in fields.iter() {
+            if !sections.contains(&field.section) {
+                sections.push(field.section);
+            }
+        }
+        let titles = sections.iter().map(|section| strings.get(section).to_string()).collect();
+
+        let (key_bindings, _problems) = KeyBindings::from_config(&settings.keybindings);
+        let mut state = SettingsState {
+            saved : settings.clone(),
+            settings : settings,
+            fields : fields,
+            sections : sections,
+            tabs : Tabs::new(titles),
+            list : ListView::new(Vec::new()),
+            shown : Vec::new(),
+            editing : Option::None,
+            capturing : Option::None,
+            key_bindings : key_bindings,
+            unsaved : false,
+            confirm_exit : Option::None,
+            status : String::new(),
+            strings : strings,
+            draw_batch : DrawBatch::new(),
+        };
+        state.refresh_list();
+        return state;
+    }
+
+    fn refresh_list (&mut self) {
+        let section = self.sections[self.tabs.active];
+        self.shown = (0..self.fields.len()).filter(|idx| self.fields[*idx].section == section).collect();
+        self.list.items = self.shown.iter().map(|idx| {
+            let field = &self.fields[*idx];
+            let restart = if field.restart { " *" } else { "" };
+            let value = match &field.value {
+                FieldValue::KeyBinding { command } => self.key_bindings.describe(*command).unwrap_or_else(|| self.strings.get("settings.unbound").to_string()),
+                _ => field.display_value(&self.settings, &self.strings),
+            };
+            format!("{:<22} {}{}", field.label, value, restart)
+        }).collect();
+        self.list.selected = self.list.selected.min(self.shown.len().saturating_sub(1));
+    }
+
+    fn window (ctx : &BTerm) -> Panel {
+        let screen_size = ctx.get_char_size();
+        return Panel::new(Rect::with_size(4, 2, screen_size.0 as i32 - 9, screen_size.1 as i32 - 4), true, Justification::CENTER, Option::None);
+    }
+
+    fn tab_area (window : &Panel) -> Panel {
+        let content = window.content();
+        return window.child(Rect::with_size(0, 0, content.width(), content.height() - 2));
+    }
+
+    /// Applies the settings that can change without a restart.
+    fn apply (&self, ctx : &mut BTerm) {
+        ctx.post_scanlines = self.settings.graphical.post_processing.scan_lines;
+        ctx.post_screenburn = self.settings.graphical.post_processing.screen_burn;
+    }
+
+    fn save (&mut self) {
+        match create_new_config_file(self.settings.clone()) {
+            Ok(_v) => {
+                self.saved = self.settings.clone();
+                self.unsaved = false;
+                self.status = self.strings.get("settings.saved").to_string();
+                info!("Saved settings");
+            },
+            Err(e) => {
+                self.status = e.clone();
+                error!("Error saving settings : {}", e);
+            },
+        }
+    }
+
+    fn close (&self) -> StateAction {
+        return StateAction::PopWithResult(StateResult::Settings(Box::new(self.settings.clone())), Option::from("Closed settings.".to_string()));
+    }
+
+    /// Puts back the saved settings and closes the screen.
+    fn discard (&mut self, ctx : &mut BTerm) -> StateAction {
+        self.settings = self.saved.clone();
+        self.apply(ctx);
+        return self.close();
+    }
+
+    /// Changes the selected setting by one step, or starts typing into it.
+    fn edit_selected (&mut self, ctx : &mut BTerm, direction : i32) {
+        let idx = match self.shown.get(self.list.selected) {
+            Some(idx) => *idx,
+            None => return,
+        };
+        let field = &self.fields[idx];
+        match &field.value {
+            FieldValue::Text { get, .. } => {
+                let mut input = TextInput::new(64);
+                input.text = get(&self.settings);
+                self.editing = Option::from(input);
+                return;
+            },
+            FieldValue::KeyBinding { command } => {
+                self.capturing = Option::from(*command);
+                return;
+            },
+            _ => field.adjust(&mut self.settings, direction),
+        }
+        self.unsaved = true;
+        // also clears the status, unless the new preset conflicts with overridden keys
+        self.rebuild_key_bindings();
+        self.apply(ctx);
+    }
+
+    /// Rebuilds the key bindings from the settings and reports any conflicts in the status line.
+    fn rebuild_key_bindings (&mut self) {
+        let (key_bindings, problems) = KeyBindings::from_config(&self.settings.keybindings);
+        self.key_bindings = key_bindings;
+        self.status = problems.join("; ");
+    }
+
+    /// Binds the chord to the command, replacing the keys the preset gives it.
+    fn bind (&mut self, command : Command, chord : KeyChord) {
+        self.settings.keybindings.bindings.insert(command.name().to_string(), vec![chord.to_string()]);
+        self.unsaved = true;
+        self.rebuild_key_bindings();
+    }
+
+    /// Drops the selected command's override so it uses the preset's keys again.
+    fn reset_selected_binding (&mut self) {
+        let idx = match self.shown.get(self.list.selected) {
+            Some(idx) => *idx,
+            None => return,
+        };
+        match &self.fields[idx].value {
+            FieldValue::KeyBinding { command } => {
+                if self.settings.keybindings.bindings.remove(command.name()).is_some() {
+                    self.unsaved = true;
+                }
+                self.rebuild_key_bindings();
+            },
+            _ => {},
+        }
+    }
+
+    fn finish_text_edit (&mut self, text : String) {
+        let idx = match self.shown.get(self.list.selected) {
+            Some(idx) => *idx,
+            None => return,
+        };
+        match &self.fields[idx].value {
+            FieldValue::Text { set, .. } => {
+                match set(&mut self.settings, text) {
+                    Ok(_v) => {
+                        self.unsaved = true;
+                        self.status = String::new();
+                    },
+                    Err(e) => self.status = e,
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+impl State for SettingsState {
+
+    fn init (&mut self) {}
+
+    fn on_enter (&mut self) {}
+
+    fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, _delta : DeltaTime) -> StateAction {
+        let window = SettingsState::window(ctx);
+
+        match self.confirm_exit.as_mut() {
+            Some(modal) => {
+                match modal.update(&input, window.bounds) {
+                    ModalResult::Pending => {},
+                    ModalResult::Cancelled => self.confirm_exit = Option::None,
+                    ModalResult::Chosen(0) => {
+                        self.save();
+                        return self.close();
+                    },
+                    ModalResult::Chosen(1) => return self.discard(ctx),
+                    ModalResult::Chosen(_) => self.confirm_exit = Option::None,
+                }
+                return StateAction::NoAction;
+            },
+            None => {},
+        }
+
+        match self.editing.as_mut() {
+            Some(text_input) => {
+                if input.key == Some(VirtualKeyCode::Escape) {
+                    self.editing = Option::None;
+                } else if text_input.update(&input) {
+                    let text = text_input.text.clone();
+                    self.editing = Option::None;
+                    self.finish_text_edit(text);
+                    self.refresh_list();
+                }
+                return StateAction::NoAction;
+            },
+            None => {},
+        }
+
+        match self.capturing {
+            Some(command) => {
+                if input.key == Some(VirtualKeyCode::Escape) {
+                    self.capturing = Option::None;
+                } else {
+                    match KeyChord::from_input(&input) {
+                        Some(chord) => {
+                            self.capturing = Option::None;
+                            self.bind(command, chord);
+                            self.refresh_list();
+                        },
+                        None => {},
+                    }
+                }
+                return StateAction::NoAction;
+            },
+            None => {},
+        }
+
+        let console = self.settings.graphical.console.clone();
+        match input.key {
+            Some(VirtualKeyCode::Escape) => {
+                if self.unsaved {
+                    let strings = &self.strings;
+                    let options = vec![strings.get("settings.save").to_string(), strings.get("settings.discard").to_string(), strings.get("settings.cancel").to_string()];
+                    self.confirm_exit = Option::from(Modal::new(strings.get("settings.unsaved"), vec![strings.get("settings.save_question").to_string()], options));
+                    return StateAction::NoAction;
+                }
+                return self.close();
+            },
+            Some(VirtualKeyCode::S) if input.ctrl => self.save(),
+            Some(VirtualKeyCode::Left) => self.edit_selected(ctx, -1),
+            Some(VirtualKeyCode::Right) => self.edit_selected(ctx, 1),
+            Some(VirtualKeyCode::Back) | Some(VirtualKeyCode::Delete) => self.reset_selected_binding(),
+            _ => {},
+        }
+
+        let tab_area = SettingsState::tab_area(&window);
+        if self.tabs.update(&input, &tab_area) {
+            self.list.selected = 0;
+        }
+        match self.list.update(&input, &self.tabs.content(&tab_area)) {
+            Some(_idx) => self.edit_selected(ctx, 1),
+            None => {},
+        }
+        self.refresh_list();
+        if self.settings.graphical.console != console {
+            return StateAction::ResizeConsoles(self.settings.graphical.console.clone());
+        }
+        return StateAction::NoAction;
+    }
+
+    fn render (&mut self, ctx : &mut BTerm) {
+        let window = SettingsState::window(ctx);
+        let tab_area = SettingsState::tab_area(&window);
+        let list_area = self.tabs.content(&tab_area);
+
+        self.draw_batch.target(1);
+        self.draw_batch.cls();
+        self.draw_batch.target(0);
+        self.draw_batch.cls();
+        draw_window(&mut self.draw_batch, &window, self.strings.get("settings.title"));
+        self.tabs.draw(&mut self.draw_batch, &tab_area);
+        self.list.draw(&mut self.draw_batch, &list_area);
+
+        match &self.editing {
+            Some(text_input) => {
+                let row = (self.list.selected - self.list.scroll) as i32;
+                text_input.draw(&mut self.draw_batch, &list_area.child(Rect::with_size(23, row, list_area.width() - 23, 1)));
+            },
+            None => {},
+        }
+
+        let bottom = window.content().height() - 1;
+        let help = if !self.status.is_empty() {
+            self.status.clone()
+        } else if self.capturing.is_some() {
+            self.strings.get("settings.help.capturing").to_string()
+        } else if self.editing.is_some() {
+            self.strings.get("settings.help.editing").to_string()
+        } else {
+            self.strings.get("settings.help").to_string()
+        };
+        Label::new(help).draw(&mut self.draw_batch, &window.row(bottom));
+        Label::new(self.strings.get("settings.restart_note")).draw(&mut self.draw_batch, &window.row(bottom - 1));
+
+        match &self.confirm_exit {
+            Some(modal) => modal.draw(&mut self.draw_batch, window.bounds),
+            None => {},
+        }
+
+        let draw_result = self.draw_batch.submit(1);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error submitting batch : {}", e);
+            },
+        }
+        let draw_result = render_draw_buffer(ctx);
+        match draw_result {
+            Ok(_v) => {},
+            Err(e) => {
+                error!("Error on rendering draw buffer : {}", e);
+            },
+        }
+    }
+
+    fn on_exit (&mut self) {}
+}
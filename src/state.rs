@@ -1,22 +1,52 @@
 use std::collections::VecDeque;
 use std::collections::HashMap;
 use bracket_lib::prelude::{BTerm, GameState, VirtualKeyCode};
+use crate::config::{Settings, ConsoleLayout, ConsoleSettings};
+use crate::raw::SharedRaws;
+use crate::input::Command;
 
+/// What the manager should do after a state updates. The optional strings are written to the debug
+/// log only, so they stay in English and name items by their id rather than by translated text.
 pub enum StateAction {
     NoAction,
     Push(Box<dyn State>, Option<String>),
     Pop(Option<String>),
+    /// Pops the state and hands a result to the state beneath it.
+    PopWithResult(StateResult, Option<String>),
     PopAmount(u32, Option<String>),
     Switch(Box<dyn State>, Option<String>),
+    /// Refits the consoles to new console settings.
+    ResizeConsoles(ConsoleSettings),
 	Exit,
 }
 
+#[derive(Debug, Clone)]
 pub enum WorldAction {
     NoAction,
     PassInventory(HashMap<u32,u32>),
     PlayerEquipItem(u32),
+    PlayerUseItem(u32),
+    PlayerDropItem(u32),
 }
 
+/// What a popped state hands back to the state beneath it.
+#[derive(Clone)]
+pub enum StateResult {
+    /// The state was closed without a result.
+    None,
+    /// Something for the world to carry out.
+    World(WorldAction),
+    /// Index of the option the player chose.
+    Choice(usize),
+    /// Text the player entered.
+    Text(String),
+    /// Settings as they were when the settings screen closed.
+    Settings(Box<Settings>),
+    /// Raws the game ended with, which may have been reloaded while it ran.
+    Raws(SharedRaws),
+}
+
+#[derive(Default)]
 pub struct CurrentInput {
     pub key : Option<VirtualKeyCode>,
     pub alt : bool,
@@ -24,29 +54,34 @@ pub struct CurrentInput {
     pub ctrl : bool,
     pub mouse_pos : (i32, i32),
     pub left_click : bool,
-}
-
-impl Default for CurrentInput {
-    fn default() -> Self {
-        CurrentInput {
-            key : None,
-            alt : false,
-            shift : false,
-            ctrl : false,
-            mouse_pos : (0,0),
-            left_click : false,
-        }
-    }
+    /// Command the pressed key is bound to, filled in by states that use `KeyBindings`.
+    pub command : Option<Command>,
 }
 
 #[derive(Default)]
 pub struct DeltaTime (pub f32);
 
+/// Size of the consoles in tiles.
+#[derive(Default)]
+pub struct ScreenSize (pub u32, pub u32);
+
+/// Number of turns the player has taken.
+#[derive(Default)]
+pub struct Turn (pub u64);
+
 pub trait State {
     /// Called when this state is pushed to the stack.
     fn init (&mut self);
     /// Called when this state become the top of the stack.
     fn on_enter (&mut self);
+    /// Called after `on_enter` when the state above this one is popped, with what it returned.
+    fn on_resume (&mut self, _result : StateResult) {}
+    /// Transparent states are drawn over the states beneath them instead of replacing them.
+    fn is_transparent (&self) -> bool {
+        return false;
+    }
+    /// Called on every state in the stack when the consoles change size.
+    fn on_resize (&mut self, _screen_size : (u32, u32)) {}
     /// Called routinely.
     fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, delta : DeltaTime) -> StateAction;
     /// Called routinely after update.
@@ -57,6 +92,14 @@ pub trait State {
 
 pub struct Manager {
     pub states : VecDeque<Box<dyn State>>,
+    /// Fits the consoles to the window when it is resized.
+    pub layout : Option<ConsoleLayout>,
+}
+
+impl Default for Manager {
+    fn default () -> Self {
+        return Manager::new();
+    }
 }
 
 impl Manager {
@@ -64,9 +107,15 @@ impl Manager {
     pub fn new () -> Self {
         Manager {
             states : VecDeque::new(),
+            layout : Option::None,
         }
     }
 
+    pub fn with_layout (mut self, layout : ConsoleLayout) -> Self {
+        self.layout = Option::from(layout);
+        return self;
+    }
+
     /// Pushes a new state to the top of the stack.
     pub fn push (&mut self, state : Box<dyn State>, message : Option<String>) {
         info!("Pushing state");
@@ -93,11 +142,19 @@ impl Manager {
         }
         self.states[0].on_exit();
         self.states.pop_front();
-        if self.states.len() >= 1 {
+        if !self.states.is_empty() {
             self.states[0].on_enter();
         }
     }
 
+    /// Pops the state from the top of the stack and passes its result to the new top state.
+    pub fn pop_with_result (&mut self, result : StateResult, message : Option<String>) {
+        self.pop(message);
+        if !self.states.is_empty() {
+            self.states[0].on_resume(result);
+        }
+    }
+
     /// Pops a number of states from the top of the stack. If no states are left on the stack,
     /// it exits the program.
     pub fn pop_amount (&mut self, amt: u32, message : Option<String>) {
@@ -127,6 +184,25 @@ impl Manager {
         info!("Switching state")
     }
 
+    /// Switches the console layout to new settings and tells every state if the consoles changed size.
+    pub fn resize_consoles (&mut self, ctx : &mut BTerm, settings : ConsoleSettings) {
+        let resized = match self.layout.as_mut() {
+            Some(layout) => layout.apply(ctx, settings),
+            None => Option::None,
+        };
+        match resized {
+            Some(screen_size) => self.on_resize(screen_size),
+            None => {},
+        }
+    }
+
+    /// Tells every state in the stack that the consoles changed size.
+    fn on_resize (&mut self, screen_size : (u32, u32)) {
+        for state in self.states.iter_mut() {
+            state.on_resize(screen_size);
+        }
+    }
+
     /// Pops all states of the stack and exits the program.
     pub fn exit (&mut self) {
         let length = self.states.len();
@@ -137,6 +213,22 @@ impl Manager {
         info!("Exiting...")
     }
 
+    /// Renders the top state, and beneath it every state showing through transparent ones, bottom first.
+    pub fn render (&mut self, ctx : &mut BTerm) {
+        for i in (0..=self.lowest_visible()).rev() {
+            self.states[i].render(ctx);
+        }
+    }
+
+    /// Index of the deepest state that shows on screen: the first one from the top that isn't transparent.
+    fn lowest_visible (&self) -> usize {
+        let mut lowest = 0;
+        while lowest + 1 < self.states.len() && self.states[lowest].is_transparent() {
+            lowest += 1;
+        }
+        return lowest;
+    }
+
     pub fn get_input (&self, ctx : &mut BTerm) -> CurrentInput {
         CurrentInput {
             key : ctx.key,
@@ -145,6 +237,7 @@ impl Manager {
             ctrl : ctx.control,
             mouse_pos : ctx.mouse_pos,
             left_click : ctx.left_click,
+            command : None,
         }
     }
 }
@@ -152,26 +245,122 @@ impl Manager {
 impl GameState for Manager {
 
     fn tick (&mut self, ctx: &mut BTerm) {
+        let resized = match self.layout.as_mut() {
+            Some(layout) => layout.update(ctx),
+            None => Option::None,
+        };
+        match resized {
+            Some(screen_size) => self.on_resize(screen_size),
+            None => {},
+        }
+
         let input = self.get_input(ctx);
         let action = self.states[0].update(ctx, input, DeltaTime(ctx.frame_time_ms));
-        self.states[0].render(ctx);
+        self.render(ctx);
 
         match action {
             StateAction::NoAction => {},
             StateAction::Pop(msg) => self.pop(msg),
+            StateAction::PopWithResult(result, msg) => {
+                // closing the settings screen may restore the console size it was opened with
+                match &result {
+                    StateResult::Settings(settings) => self.resize_consoles(ctx, settings.graphical.console.clone()),
+                    _ => {},
+                }
+                self.pop_with_result(result, msg);
+            },
             StateAction::PopAmount(number, msg) => self.pop_amount(number, msg),
             StateAction::Push(new_state, msg) => self.push(new_state, msg),
             StateAction::Switch(new_state, msg) => self.switch(new_state, msg),
+            StateAction::ResizeConsoles(settings) => self.resize_consoles(ctx, settings),
             StateAction::Exit => {self.exit()},
         }
 
-        if self.states.len() == 0 {
+        if self.states.is_empty() {
             ctx.quit();
             info!("Terminated.");
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    /// Records the calls the manager makes on it.
+    struct Recorder {
+        name : &'static str,
+        transparent : bool,
+        calls : Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn boxed (name : &'static str, transparent : bool, calls : &Rc<RefCell<Vec<String>>>) -> Box<dyn State> {
+            return Box::new(Recorder { name : name, transparent : transparent, calls : calls.clone() });
+        }
+
+        fn record (&self, call : String) {
+            self.calls.borrow_mut().push(format!("{} {}", self.name, call));
+        }
+    }
+
+    impl State for Recorder {
+        fn init (&mut self) {}
+        fn on_enter (&mut self) {
+            self.record("enter".to_string());
+        }
+        fn on_resume (&mut self, result : StateResult) {
+            match result {
+                StateResult::Choice(idx) => self.record(format!("resume choice {}", idx)),
+                _ => self.record("resume".to_string()),
+            }
+        }
+        fn is_transparent (&self) -> bool {
+            return self.transparent;
+        }
+        fn update (&mut self, _ctx : &mut BTerm, _input : CurrentInput, _delta : DeltaTime) -> StateAction {
+            return StateAction::NoAction;
+        }
+        fn render (&mut self, _ctx : &mut BTerm) {}
+        fn on_exit (&mut self) {
+            self.record("exit".to_string());
+        }
+    }
+
+    #[test]
+    fn popped_results_reach_the_state_beneath () {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = Manager::new();
+        manager.push(Recorder::boxed("game", false, &calls), Option::None);
+        manager.push(Recorder::boxed("menu", true, &calls), Option::None);
+
+        manager.pop_with_result(StateResult::Choice(2), Option::None);
+        assert_eq!(*calls.borrow(), vec!["menu exit", "game enter", "game resume choice 2"]);
+        assert_eq!(manager.states.len(), 1);
+    }
+
+    #[test]
+    fn transparent_states_show_the_states_beneath () {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = Manager::new();
+        manager.push(Recorder::boxed("title", false, &calls), Option::None);
+        manager.push(Recorder::boxed("game", false, &calls), Option::None);
+        assert_eq!(manager.lowest_visible(), 0);
+
+        manager.push(Recorder::boxed("inventory", true, &calls), Option::None);
+        manager.push(Recorder::boxed("dialog", true, &calls), Option::None);
+        assert_eq!(manager.lowest_visible(), 2);
+
+        //a stack of only transparent states still stops at the bottom
+        let mut overlays = Manager::new();
+        overlays.push(Recorder::boxed("inventory", true, &calls), Option::None);
+        overlays.push(Recorder::boxed("dialog", true, &calls), Option::None);
+        assert_eq!(overlays.lowest_visible(), 1);
+    }
+}
+
 pub mod time {
     use std::time::{SystemTime, UNIX_EPOCH};
 
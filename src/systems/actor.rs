@@ -1,8 +1,10 @@
-use specs::{System, Read, ReadStorage, WriteStorage};
-use bracket_lib::prelude::VirtualKeyCode;
+use specs::{System, Read, Write, ReadStorage, WriteStorage};
 use crate::components::basic::{Position, Actor};
-use crate::components::tag::PlayerTag;
-use crate::state::CurrentInput;
+use crate::components::tag::{PlayerTag, BlocksTile};
+use crate::state::{CurrentInput, Turn};
+use crate::systems::camera::{Camera, CameraMode};
+use crate::systems::spatial::SpatialIndex;
+use crate::input::Command;
 
 pub struct PlayerMoveSystem;
 
@@ -12,19 +14,38 @@ impl <'a> System <'a> for PlayerMoveSystem {
         WriteStorage <'a, Position>,
         WriteStorage <'a, Actor>,
         Read <'a, CurrentInput>,
+        Write <'a, Turn>,
+        Read <'a, Camera>,
+        Read <'a, SpatialIndex>,
+        ReadStorage <'a, BlocksTile>,
     );
 
-    fn run (&mut self, (playertag, mut positions, mut actors, current_input) : Self::SystemData) {
+    fn run (&mut self, (playertag, mut positions, _actors, current_input, mut turn, camera, spatial_index, blockers) : Self::SystemData) {
         use specs::Join;
+
+        // movement keys move the look cursor instead
+        if camera.mode == CameraMode::Look {
+            return;
+        }
             
+        let delta = match current_input.command {
+            Some(Command::Wait) => {
+                turn.0 += 1;
+                return;
+            },
+            Some(command) => match command.direction() {
+                Some(delta) => delta,
+                None => return,
+            },
+            None => return,
+        };
+
         for (_playertag, position) in (&playertag, &mut positions).join() {
-            match current_input.key {
-                Some(VirtualKeyCode::Up) => {position.y -= 1;},
-                Some(VirtualKeyCode::Down) => {position.y += 1},
-                Some(VirtualKeyCode::Left) => {position.x -= 1},
-                Some(VirtualKeyCode::Right) => {position.x += 1},
-                None => {},
-                _ => {},
+            let target = (position.x + delta.0, position.y + delta.1);
+            if !spatial_index.is_blocked(target, &blockers) {
+                position.x = target.0;
+                position.y = target.1;
+                turn.0 += 1;
             }
         }
     }
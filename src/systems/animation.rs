@@ -1,8 +1,7 @@
-use specs::{System, Read, ReadStorage, WriteStorage};
+use specs::{System, Read, WriteStorage};
 
 use crate::state::DeltaTime;
 use crate::components::basic::{Renderable, ColorLerp, CycleAnimation};
-use bracket_lib::prelude::RGB;
 
 pub struct AnimationSystem;
 
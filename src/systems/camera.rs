@@ -0,0 +1,245 @@
+use specs::{System, Read, Write, ReadStorage};
+use bracket_lib::prelude::{Rect, Point};
+
+use crate::components::basic::Position;
+use crate::components::gui::{Panel, Justification};
+use crate::components::tag::PlayerTag;
+use crate::level_generation::map::Map;
+use crate::state::CurrentInput;
+use crate::input::Command;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum CameraMode {
+    /// The camera follows the player.
+    Follow,
+    /// The camera follows a free cursor used to inspect the map.
+    Look,
+}
+
+/// Decides which part of the map is drawn and where on the screen it goes.
+#[derive(Debug)]
+pub struct Camera {
+    /// Map coordinates of the top left tile of the viewport.
+    pub x : i32,
+    pub y : i32,
+    /// Area of the screen the map is drawn into; excludes space taken by docked panels.
+    pub viewport : Rect,
+    /// How far the target can stray from the center of the viewport before the camera moves.
+    pub dead_zone : (i32, i32),
+    pub mode : CameraMode,
+    /// Map coordinates of the look cursor.
+    pub cursor : (i32, i32),
+    screen_size : (u32, u32),
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new((80, 40))
+    }
+}
+
+impl Camera {
+    pub fn new (screen_size : (u32, u32)) -> Self {
+        Camera {
+            x : 0,
+            y : 0,
+            viewport : Rect::with_size(0, 0, screen_size.0 as i32, screen_size.1 as i32),
+            dead_zone : (screen_size.0 as i32 / 10, screen_size.1 as i32 / 8),
+            mode : CameraMode::Follow,
+            cursor : (0, 0),
+            screen_size : screen_size,
+        }
+    }
+
+    /// Changes the size of the screen the viewport is fitted into.
+    pub fn resize (&mut self, screen_size : (u32, u32)) {
+        self.screen_size = screen_size;
+        self.dead_zone = (screen_size.0 as i32 / 10, screen_size.1 as i32 / 8);
+    }
+
+    /// Converts map coordinates to screen coordinates.
+    pub fn map_to_screen (&self, position : (i32, i32)) -> (i32, i32) {
+        return (self.viewport.x1 + position.0 - self.x, self.viewport.y1 + position.1 - self.y);
+    }
+
+    /// Converts screen coordinates to map coordinates.
+    pub fn screen_to_map (&self, position : (i32, i32)) -> (i32, i32) {
+        return (position.0 - self.viewport.x1 + self.x, position.1 - self.viewport.y1 + self.y);
+    }
+
+    /// Returns true if the given map coordinates fall inside the viewport.
+    pub fn in_view (&self, position : (i32, i32)) -> bool {
+        let screen = self.map_to_screen(position);
+        return self.viewport.point_in_rect(Point::from_tuple(screen));
+    }
+
+    /// Works out the viewport from the panels docked to the sides and bottom of the screen.
+    pub fn fit_viewport<'a, I> (&mut self, panels : I) where I : Iterator<Item = &'a Panel> {
+        let mut viewport = Rect::with_size(0, 0, self.screen_size.0 as i32, self.screen_size.1 as i32);
+        for panel in panels {
+            match panel.justification {
+                Justification::LEFT => viewport.x1 = viewport.x1.max(panel.bounds.x2 + 1),
+                Justification::RIGHT => viewport.x2 = viewport.x2.min(panel.bounds.x1),
+                Justification::BOTTOM => viewport.y2 = viewport.y2.min(panel.bounds.y1),
+                _ => {},
+            }
+        }
+        self.viewport = viewport;
+    }
+
+    /// Snaps the camera so the target is in the center of the viewport.
+    pub fn center_on (&mut self, target : (i32, i32), map_size : (usize, usize)) {
+        self.x = target.0 - self.viewport.width() / 2;
+        self.y = target.1 - self.viewport.height() / 2;
+        self.clamp(map_size);
+    }
+
+    /// Moves the camera only as much as needed to keep the target inside the dead zone.
+    pub fn follow (&mut self, target : (i32, i32), map_size : (usize, usize)) {
+        let center_x = self.x + self.viewport.width() / 2;
+        let center_y = self.y + self.viewport.height() / 2;
+        let dx = target.0 - center_x;
+        let dy = target.1 - center_y;
+
+        if dx > self.dead_zone.0 {
+            self.x += dx - self.dead_zone.0;
+        } else if dx < -self.dead_zone.0 {
+            self.x += dx + self.dead_zone.0;
+        }
+        if dy > self.dead_zone.1 {
+            self.y += dy - self.dead_zone.1;
+        } else if dy < -self.dead_zone.1 {
+            self.y += dy + self.dead_zone.1;
+        }
+        self.clamp(map_size);
+    }
+
+    /// Keeps the viewport inside the map. Maps smaller than the viewport are centered.
+    fn clamp (&mut self, map_size : (usize, usize)) {
+        let width = self.viewport.width();
+        let height = self.viewport.height();
+        let map_width = map_size.0 as i32;
+        let map_height = map_size.1 as i32;
+
+        self.x = if map_width <= width {
+            -(width - map_width) / 2
+        } else {
+            self.x.max(0).min(map_width - width)
+        };
+        self.y = if map_height <= height {
+            -(height - map_height) / 2
+        } else {
+            self.y.max(0).min(map_height - height)
+        };
+    }
+}
+
+/// Moves the camera and the look cursor.
+pub struct CameraSystem;
+
+impl <'a> System<'a> for CameraSystem {
+    type SystemData = (
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, Panel>,
+        Read<'a, Map>,
+        Read<'a, CurrentInput>,
+        Write<'a, Camera>,
+    );
+
+    fn run (&mut self, (positions, player_tag, panels, map, current_input, mut camera) : Self::SystemData) {
+        use specs::Join;
+
+        let mut player = (0, 0);
+        for (position, _player) in (&positions, &player_tag).join() {
+            player = (position.x, position.y);
+        }
+
+        camera.fit_viewport((&panels).join());
+        let map_size = (map.width, map.height);
+
+        match camera.mode {
+            CameraMode::Follow => {
+                if current_input.command == Some(Command::ToggleLook) {
+                    camera.mode = CameraMode::Look;
+                    camera.cursor = player;
+                }
+                camera.follow(player, map_size);
+            },
+            CameraMode::Look => {
+                let mut cursor = camera.cursor;
+                match current_input.command {
+                    Some(Command::ToggleLook) | Some(Command::Back) => {
+                        camera.mode = CameraMode::Follow;
+                    },
+                    Some(command) => {
+                        match command.direction() {
+                            Some(delta) => cursor = (cursor.0 + delta.0, cursor.1 + delta.1),
+                            None => {},
+                        }
+                    },
+                    None => {},
+                }
+                camera.cursor = (cursor.0.max(0).min(map.width as i32 - 1), cursor.1.max(0).min(map.height as i32 - 1));
+
+                if camera.mode == CameraMode::Look {
+                    let cursor = camera.cursor;
+                    camera.follow(cursor, map_size);
+                } else {
+                    camera.follow(player, map_size);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP_SIZE : (usize, usize) = (200, 200);
+
+    #[test]
+    fn follow_moves_only_past_the_dead_zone () {
+        let mut camera = Camera::new((80, 40));
+        camera.center_on((100, 100), MAP_SIZE);
+        assert_eq!((camera.x, camera.y), (60, 80));
+
+        camera.follow((100 + camera.dead_zone.0, 100 - camera.dead_zone.1), MAP_SIZE);
+        assert_eq!((camera.x, camera.y), (60, 80));
+
+        camera.follow((110, 90), MAP_SIZE);
+        assert_eq!((camera.x, camera.y), (62, 75));
+    }
+
+    #[test]
+    fn viewport_is_clamped_to_the_map () {
+        let mut camera = Camera::new((80, 40));
+        camera.center_on((0, 0), MAP_SIZE);
+        assert_eq!((camera.x, camera.y), (0, 0));
+        camera.center_on((199, 199), MAP_SIZE);
+        assert_eq!((camera.x, camera.y), (120, 160));
+
+        //maps smaller than the viewport are centered
+        camera.center_on((5, 5), (40, 20));
+        assert_eq!((camera.x, camera.y), (-20, -10));
+    }
+
+    #[test]
+    fn screen_and_map_coordinates_round_trip () {
+        let mut camera = Camera::new((80, 40));
+        camera.viewport = Rect::with_size(10, 2, 60, 30);
+        camera.x = 37;
+        camera.y = -4;
+
+        assert_eq!(camera.map_to_screen((37, -4)), (10, 2));
+        for position in [(0, 0), (37, -4), (96, 25), (-3, 150)].iter() {
+            assert_eq!(camera.screen_to_map(camera.map_to_screen(*position)), *position);
+            assert_eq!(camera.map_to_screen(camera.screen_to_map(*position)), *position);
+        }
+        assert!(camera.in_view((37, -4)));
+        assert!(camera.in_view((96, 25)));
+        assert!(!camera.in_view((97, 25)));
+        assert!(!camera.in_view((36, 0)));
+    }
+}
@@ -0,0 +1,138 @@
+use bracket_lib::prelude::RGB;
+use specs::{System, Read, Write};
+
+use crate::config::{Daylight, SkyPhase};
+use crate::level_generation::map::{Map, MapType};
+use crate::state::Turn;
+
+/// Keeps track of the time of day and the sky light it produces.
+pub struct TimeOfDay {
+    pub day_length : u32,
+    start_time : f32,
+    /// Key frames of the day as (fraction of day, color * intensity), sorted by time.
+    palette : Vec<(f32, RGB)>,
+    overrides : Vec<(MapType, RGB)>,
+    /// Fraction of the day that has passed; 0.0 is midnight and 0.5 is noon.
+    pub time : f32,
+    /// Light coming from the open sky.
+    pub sky_light : RGB,
+    /// Light that replaces the schedule under cover, if the biome overrides it.
+    pub biome_light : Option<RGB>,
+    /// Daylight reaching each tile: the sky light scaled by the tile's exposure in `Map::sky_map`,
+    /// and the biome light, if any, for the part of the tile that is covered.
+    pub tile_light : Vec<RGB>,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        TimeOfDay::new(&Daylight::default())
+    }
+}
+
+impl TimeOfDay {
+    pub fn new (settings : &Daylight) -> Self {
+        let palette = vec![
+            (0.0, phase_color(&settings.night)),
+            (0.25, phase_color(&settings.dawn)),
+            (0.5, phase_color(&settings.day)),
+            (0.75, phase_color(&settings.dusk)),
+        ];
+
+        let mut overrides = Vec::new();
+        for biome in settings.overrides.iter() {
+            let color = parse_color(&biome.color) * biome.intensity;
+            overrides.push((biome.map_type, color));
+        }
+
+        let day_length = if settings.day_length == 0 {
+            error!("Day length must be at least one turn; falling back on default");
+            Daylight::default().day_length
+        } else {
+            settings.day_length
+        };
+
+        let mut time_of_day = TimeOfDay {
+            day_length : day_length,
+            start_time : settings.start_time,
+            palette : palette,
+            overrides : overrides,
+            time : 0.0,
+            sky_light : RGB::from_f32(0.0, 0.0, 0.0),
+            biome_light : Option::None,
+            tile_light : Vec::new(),
+        };
+        time_of_day.update(0, MapType::Empty);
+        return time_of_day;
+    }
+
+    /// Advances the clock to the given turn and recomputes the light for the given map type.
+    pub fn update (&mut self, turn : u64, map_type : MapType) {
+        let turn_of_day = (turn % self.day_length as u64) as f32 / self.day_length as f32;
+        self.time = (self.start_time + turn_of_day).fract();
+        self.sky_light = self.sample_palette(self.time);
+
+        self.biome_light = Option::None;
+        for (biome, color) in self.overrides.iter() {
+            if *biome == map_type {
+                self.biome_light = Option::from(*color);
+            }
+        }
+    }
+
+    /// Works out the daylight of each tile from its sky exposure.
+    pub fn light_tiles (&mut self, sky_map : &[f32]) {
+        let covered = self.biome_light.unwrap_or_else(|| RGB::from_f32(0.0, 0.0, 0.0));
+        self.tile_light.clear();
+        for exposure in sky_map.iter() {
+            self.tile_light.push(self.sky_light * *exposure + covered * (1.0 - *exposure));
+        }
+    }
+
+    fn sample_palette (&self, time : f32) -> RGB {
+        let length = self.palette.len();
+        for i in 0..length {
+            let (start, start_color) = self.palette[i];
+            let (end, end_color) = if i + 1 < length {
+                self.palette[i + 1]
+            } else {
+                // wrap around to the first key frame at the end of the day
+                (self.palette[0].0 + 1.0, self.palette[0].1)
+            };
+
+            if time >= start && time < end {
+                return start_color.lerp(end_color, (time - start) / (end - start));
+            }
+        }
+        return self.palette[0].1;
+    }
+}
+
+fn phase_color (phase : &SkyPhase) -> RGB {
+    return parse_color(&phase.color) * phase.intensity;
+}
+
+fn parse_color (hex : &str) -> RGB {
+    match RGB::from_hex(hex) {
+        Ok(t) => t,
+        Err(_e) => {
+            error!("Invalid sky color \"{}\" in config; falling back on black", hex);
+            RGB::from_f32(0.0, 0.0, 0.0)
+        },
+    }
+}
+
+/// Advances the time of day with the turn counter and lights each tile by its sky exposure.
+pub struct DaylightSystem;
+
+impl <'a> System<'a> for DaylightSystem {
+    type SystemData = (
+        Read<'a, Turn>,
+        Read<'a, Map>,
+        Write<'a, TimeOfDay>,
+    );
+
+    fn run (&mut self, (turn, map, mut time_of_day) : Self::SystemData) {
+        time_of_day.update(turn.0, map.map_type);
+        time_of_day.light_tiles(&map.sky_map);
+    }
+}
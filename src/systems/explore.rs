@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+use specs::{System, Read, Write, ReadStorage, WriteStorage, Entities, Entity};
+use bracket_lib::prelude::{Algorithm2D, BaseMap, DijkstraMap, Point};
+
+use crate::components::basic::{Position, Actor, ItemWrapper};
+use crate::components::tag::{PlayerTag, BlocksTile, Hostile};
+use crate::level_generation::map::Map;
+use crate::level_generation::map::tile::feature_name;
+use crate::state::{CurrentInput, DeltaTime, Turn};
+use crate::systems::camera::{Camera, CameraMode};
+use crate::systems::spatial::SpatialIndex;
+use crate::systems::pathing::PathingMap;
+use crate::systems::travel::{Travel, STEP_DELAY};
+use crate::systems::sight::Visibility;
+use crate::game_log::{GameLog, LogCategory};
+use crate::input::Command;
+use crate::locale::SharedStrings;
+use crate::raw::{RawMaster, SharedRaws, ItemRaw, ItemType};
+
+/// Auto-explore won't run while the player's health is at or below this fraction of its maximum.
+const LOW_HEALTH : f32 = 0.5;
+/// Feature tiles closer than this to a known feature of the same kind belong to the same place.
+const FEATURE_SPREAD : i32 = 8;
+/// Farthest the explore map searches for unexplored tiles.
+const MAX_EXPLORE_DEPTH : f32 = 2000.0;
+
+/// What the player has seen, and whether they are auto-exploring.
+#[derive(Default)]
+pub struct Exploration {
+    pub active : bool,
+    /// Walk to visible items and pick them up rather than stopping when one is seen.
+    pub auto_pickup : bool,
+    /// Set when auto-explore wants the item under the player picked up this turn.
+    pub pickup_requested : bool,
+    /// Items and hostiles that have already been seen.
+    seen : HashSet<Entity>,
+    /// Kind and position of every feature discovered so far.
+    features : Vec<(&'static str, (i32, i32))>,
+    since_last_step : f32,
+}
+
+impl Exploration {
+    pub fn new (auto_pickup : bool) -> Self {
+        Exploration {
+            auto_pickup : auto_pickup,
+            ..Default::default()
+        }
+    }
+
+    fn stop (&mut self) {
+        self.active = false;
+        self.pickup_requested = false;
+    }
+}
+
+/// Whether picking the item up takes it off the map. Items missing from the raws are left where they lie,
+/// so auto-explore shouldn't wait on them.
+fn can_pick_up (raws : &RawMaster, item : &ItemRaw) -> bool {
+    return item.item_type == ItemType::Currency || raws.get_item_id(&item.id).is_some();
+}
+
+/// Reveals the tiles the player can see and walks them toward the nearest unexplored tile while
+/// auto-exploring.
+pub struct ExploreSystem;
+
+impl <'a> System<'a> for ExploreSystem {
+    type SystemData = (
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, Hostile>,
+        ReadStorage<'a, BlocksTile>,
+        ReadStorage<'a, ItemWrapper>,
+        ReadStorage<'a, Actor>,
+        WriteStorage<'a, Position>,
+        Write<'a, Map>,
+        Read<'a, SpatialIndex>,
+        Read<'a, Camera>,
+        Read<'a, Visibility>,
+        Read<'a, CurrentInput>,
+        Read<'a, DeltaTime>,
+        Write<'a, Turn>,
+        Write<'a, Travel>,
+        Write<'a, Exploration>,
+        Write<'a, GameLog>,
+        Read<'a, SharedStrings>,
+        Read<'a, SharedRaws>,
+        Entities<'a>,
+    );
+
+    fn run (&mut self, (player_tag, hostiles, blockers, item_wrappers, actors, mut positions, mut map, spatial_index, camera, visibility, current_input, delta, mut turn, mut travel, mut exploration, mut game_log, strings, raws, entities) : Self::SystemData) {
+        use specs::Join;
+
+        let mut player = Option::None;
+        for (entity, _player, position) in (&entities, &player_tag, &positions).join() {
+            player = Option::from((entity, (position.x, position.y)));
+        }
+        let (player_entity, player_position) = match player {
+            Some(player) => player,
+            None => return,
+        };
+
+        // pressing any other key or clicking takes back control
+        let other_key = current_input.key.is_some() && current_input.command != Option::from(Command::AutoExplore);
+        if exploration.active && (other_key || current_input.left_click) {
+            exploration.stop();
+        }
+
+        let low_health = match actors.get(player_entity) {
+            Some(actor) => actor.current_health as f32 <= actor.max_health as f32 * LOW_HEALTH,
+            None => false,
+        };
+        if current_input.command == Option::from(Command::AutoExplore) && camera.mode == CameraMode::Follow {
+            if low_health {
+                game_log.push(LogCategory::Status, strings.get("log.too_hurt_to_explore"), turn.0);
+            } else {
+                exploration.active = true;
+                // explore right away
+                exploration.since_last_step = STEP_DELAY;
+                travel.stop();
+            }
+        }
+
+        //reveal what the player can see and note anything new
+        let mut interruptions : Vec<String> = Vec::new();
+        for (x, y) in visibility.tiles.iter() {
+            let point = Point::new(*x, *y);
+            let idx = point.x as usize + point.y as usize * map.width;
+            if !map.revealed[idx] {
+                map.revealed[idx] = true;
+                match feature_name(map.tiles[idx]) {
+                    Some(name) => {
+                        let known = exploration.features.iter().any(|(known_name, known_position)| {
+                            *known_name == name && (known_position.0 - point.x).abs() <= FEATURE_SPREAD && (known_position.1 - point.y).abs() <= FEATURE_SPREAD
+                        });
+                        exploration.features.push((name, (point.x, point.y)));
+                        if !known {
+                            game_log.push(LogCategory::Discovery, strings.get(&format!("log.discover.{}", name)), turn.0);
+                            interruptions.push(strings.get(&format!("feature.{}", name)).to_string());
+                        }
+                    },
+                    None => {},
+                }
+            }
+
+            for entity in spatial_index.entities_at((point.x, point.y)) {
+                let is_hostile = hostiles.contains(*entity);
+                let item = item_wrappers.get(*entity);
+                if !(is_hostile || item.is_some()) || exploration.seen.contains(entity) {
+                    continue;
+                }
+                exploration.seen.insert(*entity);
+                if is_hostile {
+                    interruptions.push(strings.get("log.something_hostile").to_string());
+                }
+                match item {
+                    Some(item_wrapper) if !exploration.auto_pickup => interruptions.push(strings.item_name(&item_wrapper.item_data)),
+                    _ => {},
+                }
+            }
+        }
+
+        if !exploration.active {
+            return;
+        }
+        if !interruptions.is_empty() {
+            exploration.stop();
+            game_log.push(LogCategory::Status, strings.format("log.spot_and_stop_exploring", &[&interruptions.join(", ")]), turn.0);
+            return;
+        }
+        if low_health {
+            exploration.stop();
+            game_log.push(LogCategory::Status, strings.get("log.hurt_stops_exploring"), turn.0);
+            return;
+        }
+
+        exploration.since_last_step += delta.0;
+        if exploration.since_last_step < STEP_DELAY {
+            return;
+        }
+        exploration.since_last_step = 0.0;
+
+        //pick up whatever is underfoot before moving on
+        let items_here = spatial_index.entities_at(player_position).iter().any(|entity| {
+            return item_wrappers.get(*entity).is_some_and(|item_wrapper| can_pick_up(&raws, &item_wrapper.item_data));
+        });
+        if exploration.auto_pickup && items_here {
+            exploration.pickup_requested = true;
+            return;
+        }
+
+        //head downhill on a map that grows outward from every unexplored tile
+        let pathing_map = PathingMap::new(&map, &spatial_index, &blockers);
+        let mut targets : Vec<usize> = (0..map.revealed.len()).filter(|idx| !map.revealed[*idx]).collect();
+        if exploration.auto_pickup {
+            for (item_wrapper, position) in (&item_wrappers, &positions).join() {
+                if !can_pick_up(&raws, &item_wrapper.item_data) {
+                    continue;
+                }
+                let idx = position.x as usize + position.y as usize * map.width;
+                if map.revealed[idx] {
+                    targets.push(idx);
+                }
+            }
+        }
+        let explore_map = DijkstraMap::new(map.width, map.height, &targets, &pathing_map, MAX_EXPLORE_DEPTH);
+        let player_idx = pathing_map.point2d_to_index(Point::from_tuple(player_position));
+        let next = pathing_map.get_available_exits(player_idx).iter()
+            .map(|(idx, _cost)| *idx)
+            .filter(|idx| explore_map.map[*idx] < f32::MAX)
+            .min_by(|a, b| explore_map.map[*a].partial_cmp(&explore_map.map[*b]).unwrap());
+        let next = match next {
+            Some(idx) => pathing_map.index_to_point2d(idx),
+            None => {
+                exploration.stop();
+                game_log.push(LogCategory::System, strings.get("log.explored_everything"), turn.0);
+                return;
+            },
+        };
+
+        match positions.get_mut(player_entity) {
+            Some(position) => {
+                position.x = next.x;
+                position.y = next.y;
+            },
+            None => return,
+        }
+        turn.0 += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use bracket_lib::prelude::RGB;
+    use crate::level_generation::map::MapType;
+    use crate::level_generation::map::tile::TileType;
+    use crate::locale::Strings;
+    use crate::systems::spatial::SpatialIndexSystem;
+
+    #[test]
+    fn items_missing_from_the_raws_are_walked_past () {
+        let mut raws = RawMaster::empty();
+        raws.load_raws().unwrap();
+        let mut orb = raws.get_item(raws.get_item_id("diamond").unwrap()).unwrap().clone();
+        orb.id = "mystery_orb".to_string();
+
+        let mut world = World::new();
+        world.register::<PlayerTag>();
+        world.register::<Hostile>();
+        world.register::<BlocksTile>();
+        world.register::<ItemWrapper>();
+        world.register::<Actor>();
+        world.register::<Position>();
+        let mut map = Map::new(5, 1, "explore".to_string(), MapType::Cavern, RGB::from_f32(0.0, 0.0, 0.0));
+        map.tiles = vec![TileType::Floor; 5];
+        map.revealed = vec![true; 5];
+        world.insert(map);
+        world.insert(Arc::new(raws));
+        world.insert(Arc::new(Strings::load("en")));
+        let mut exploration = Exploration::new(true);
+        exploration.active = true;
+        world.insert(exploration);
+
+        world.create_entity().with(PlayerTag).with(Position::new(0, 0)).build();
+        world.create_entity().with(ItemWrapper::new(orb.clone())).with(Position::new(0, 0)).build();
+        world.create_entity().with(ItemWrapper::new(orb)).with(Position::new(4, 0)).build();
+        let mut spatial = SpatialIndexSystem::default();
+        System::setup(&mut spatial, &mut world);
+        spatial.run_now(&world);
+
+        System::setup(&mut ExploreSystem, &mut world);
+        world.write_resource::<Exploration>().since_last_step = STEP_DELAY;
+        ExploreSystem.run_now(&world);
+
+        //nothing is left to pick up or explore, so exploring ends instead of asking for a pickup every step
+        let exploration = world.read_resource::<Exploration>();
+        assert!(!exploration.pickup_requested);
+        assert!(!exploration.active);
+        assert_eq!(world.read_resource::<Turn>().0, 0);
+    }
+}
@@ -1,27 +1,80 @@
-use specs::{System, ReadStorage, WriteStorage, Read, Entities};
-use bracket_lib::prelude::VirtualKeyCode;
-use crate::components::basic::*;
+use specs::{System, WriteStorage, Read};
+use bracket_lib::prelude::Rect;
 use crate::components::gui::*;
-use crate::state::CurrentInput;
+use crate::state::{CurrentInput, ScreenSize};
+use crate::game_log::GameLog;
+use crate::input::Command;
 
-pub struct GUIUpdate;
+pub struct GUIUpdate {
+    /// Screen size the panels were last laid out for.
+    pub screen_size : (u32, u32),
+}
+
+impl GUIUpdate {
+    pub fn new (screen_size : (u32, u32)) -> Self {
+        GUIUpdate {
+            screen_size : screen_size,
+        }
+    }
+}
 
 impl <'a> System <'a> for GUIUpdate {
     type SystemData = (
         WriteStorage <'a, PlayerCard>,
+        WriteStorage <'a, MessageLogCard>,
+        WriteStorage <'a, Panel>,
         Read <'a, CurrentInput>,
+        Read <'a, GameLog>,
+        Read <'a, ScreenSize>,
     );
 
-    fn run (&mut self, (mut player_card, current_input) : Self::SystemData) {
+    fn run (&mut self, (mut player_card, mut log_cards, mut panels, current_input, game_log, screen_size) : Self::SystemData) {
         use specs::Join;
+        let screen = Rect::with_size(0, 0, screen_size.0 as i32, screen_size.1 as i32);
+
+        //fit the player card to a resized screen
+        if (screen_size.0, screen_size.1) != self.screen_size {
+            self.screen_size = (screen_size.0, screen_size.1);
+            for (card, panel) in (&player_card, &mut panels).join() {
+                panel.bounds = Rect::with_size(0, 0, screen.width() / 4, screen.height());
+                panel.parent = Option::None;
+                panel.justify(card.justification, screen);
+            }
+        }
         
-        for card in (&mut player_card).join() {
-            match current_input.key {
-                Some(VirtualKeyCode::Tab) => {
+        for (card, panel) in (&mut player_card, &mut panels).join() {
+            match current_input.command {
+                Some(Command::CyclePanel) => {
                     card.cycle_justification();
+                    let container = match panel.parent {
+                        Some(parent) => parent,
+                        None => screen,
+                    };
+                    panel.justify(card.justification, container);
                 },
                 _ => {}
             }
         }
+
+        //bottom panels fill the space between panels docked to the sides
+        let mut bottom_container = screen;
+        for panel in (&panels).join() {
+            match panel.justification {
+                Justification::LEFT => bottom_container.x1 = bottom_container.x1.max(panel.bounds.x2 + 1),
+                Justification::RIGHT => bottom_container.x2 = bottom_container.x2.min(panel.bounds.x1),
+                _ => {},
+            }
+        }
+        for (log_card, panel) in (&mut log_cards, &mut panels).join() {
+            panel.justify(Justification::BOTTOM, bottom_container);
+
+            let visible = (panel.height() - 2).max(0) as usize;
+            let max_scroll = game_log.entries.len().saturating_sub(visible);
+            match current_input.command {
+                Some(Command::ScrollLogUp) => log_card.scroll = (log_card.scroll + 1).min(max_scroll),
+                Some(Command::ScrollLogDown) => log_card.scroll = log_card.scroll.saturating_sub(1),
+                _ => {},
+            }
+        }
     }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
 use specs::{WriteStorage, System, Entities, Read};
-use crate::level_generation::map::{Map, MapType};
+use crate::level_generation::map::Map;
 use crate::level_generation::map::tile::TileType;
 use crate::components::basic::{Position, Renderable, Light, ColorLerp};
 use bracket_lib::prelude::RGB;
-use crate::systems::render::ObjectShader;
-use rand::{StdRng, SeedableRng, Rng};
+use crate::systems::render::{ObjectShader, RenderLayer};
+use crate::tileset::SpriteMap;
+use rand::StdRng;
 pub struct LevelGenSystem;
 
 impl <'a> System<'a> for LevelGenSystem {
@@ -14,19 +15,20 @@ impl <'a> System<'a> for LevelGenSystem {
         WriteStorage <'a, Light>,
         WriteStorage <'a, ColorLerp>,
         Read<'a, Map>,
+        Read<'a, SpriteMap>,
         Entities<'a>,
     );
 
-    fn run (&mut self, (mut positions, mut renderables, mut lights, mut colorlerps, map, entities) : Self::SystemData) {
+    fn run (&mut self, (mut positions, mut renderables, mut lights, mut colorlerps, map, sprites, entities) : Self::SystemData) {
             use rand::Rng;
-            let mut rng : StdRng = SeedableRng::from_seed(map.hashed_seed.to_256_bit());
+            let mut rng : StdRng = map.hashed_seed.stream("decoration");
             for x in 0..map.width {
                 for y in 0..map.height {
                     match map.tiles[x+y*map.width] {
                         TileType::Floor => {
                             let _ = entities.build_entity()
                                 .with(Position::new(x as i32, y as i32), &mut positions)
-                                .with(Renderable::new_from_char('.', RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                .with(Renderable::new_from_sprite(&sprites, "floor", RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                 .build();
                         },
                         TileType::Wall => {
@@ -39,12 +41,12 @@ impl <'a> System<'a> for LevelGenSystem {
                             if is_front_wall {
                                 let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(223, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.5, 0.5, 0.5), ObjectShader::Foreground, ObjectShader::Foreground), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "wall_front", RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.5, 0.5, 0.5), ObjectShader::Foreground, ObjectShader::Foreground), &mut renderables)
                                     .build();
                             } else {
                                 let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(219, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "wall", RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                             }
                         },
@@ -53,7 +55,7 @@ impl <'a> System<'a> for LevelGenSystem {
                             let rate: f32 = rng.gen_range::<f32>(0.5, 1.0) * 3000.0;
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(247, RGB::from_f32(0.6, 0.6, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "water", RGB::from_f32(0.6, 0.6, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .with(ColorLerp::new(RGB::from_u8(100, 100, 255), RGB::from_u8(175, 175, 255), rate, offset), &mut colorlerps)
                                     .build();
                         },
@@ -62,7 +64,7 @@ impl <'a> System<'a> for LevelGenSystem {
                             let rate: f32 = rng.gen_range::<f32>(0.5, 1.0) * 3000.0;
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(247, RGB::from_f32(0.0, 0.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "water", RGB::from_f32(0.0, 0.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .with(ColorLerp::new(RGB::from_u8(165, 165, 245), RGB::from_u8(0, 0, 200), rate, offset), &mut colorlerps)
                                     .build();
                         },
@@ -71,7 +73,7 @@ impl <'a> System<'a> for LevelGenSystem {
                             let rate: f32 = rng.gen_range::<f32>(0.5, 1.0) * 7500.0;
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(247, RGB::from_f32(1.0, 0.0, 0.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::NoShading, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "lava", RGB::from_f32(1.0, 0.0, 0.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::NoShading, ObjectShader::Background), &mut renderables)
                                     .with(Light::new(6, 1.0, RGB::from_f32(1.0, 0.0, 0.0)), &mut lights)
                                     .with(ColorLerp::new(RGB::from_u8(255, 0, 0), RGB::from_u8(105, 105, 105), rate, offset), &mut colorlerps)
                                     .build();
@@ -81,78 +83,78 @@ impl <'a> System<'a> for LevelGenSystem {
                             let rate: f32 = rng.gen_range::<f32>(0.5, 1.0) * 5000.0;
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(247, RGB::from_f32(1.0, 0.5, 0.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::NoShading, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "lava", RGB::from_f32(1.0, 0.5, 0.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::NoShading, ObjectShader::Background), &mut renderables)
                                     .with(Light::new(6, 1.0, RGB::from_f32(1.0, 0.5, 0.0)), &mut lights)
                                     .with(ColorLerp::new(RGB::from_u8(255, 0, 0), RGB::from_u8(255, 175, 0), rate, offset), &mut colorlerps)
                                     .build();
                         },
                         TileType::ShortGrass(d) => {
-                            make_grass(&entities, &mut positions, &mut renderables, false, &mut rng, x, y, d);
+                            make_grass(&entities, &mut positions, &mut renderables, &sprites, false, &mut rng, x, y, d);
                         },
                         TileType::TallGrass(d) => {
-                            make_grass(&entities, &mut positions, &mut renderables, true, &mut rng, x, y, d);
+                            make_grass(&entities, &mut positions, &mut renderables, &sprites, true, &mut rng, x, y, d);
                         },
                         TileType::SmallMushroom => {
-                            make_mushroom(&entities, &mut positions, &mut renderables, &mut lights, false, &mut rng, x, y);
+                            make_mushroom(&entities, &mut positions, &mut renderables, &sprites, &mut lights, false, &mut rng, x, y);
                         },
                         TileType::LargeMushroom => {
-                             make_mushroom(&entities, &mut positions, &mut renderables, &mut lights, true, &mut rng, x, y);
+                             make_mushroom(&entities, &mut positions, &mut renderables, &sprites, &mut lights, true, &mut rng, x, y);
                         },
                         TileType::ThinWebs => {
-                            make_web(&entities, &mut positions, &mut renderables, false, &mut rng, x, y);
+                            make_web(&entities, &mut positions, &mut renderables, &sprites, false, &mut rng, x, y);
                         },
                         TileType::ThickWebs => {
-                            make_web(&entities, &mut positions, &mut renderables, true, &mut rng, x, y);
+                            make_web(&entities, &mut positions, &mut renderables, &sprites, true, &mut rng, x, y);
                         },
                         TileType::EggSac => {
-                            make_egg_sac(&entities, &mut positions, &mut renderables, x, y);
+                            make_egg_sac(&entities, &mut positions, &mut renderables, &sprites, x, y);
                         },
                         TileType::TentTopCenter => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(196, RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "tent_top_center", RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::TentTopLeft => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(218, RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "tent_top_left", RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::TentTopRight => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(191, RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "tent_top_right", RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::TentBottomCenter => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(205, RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "tent_bottom_center", RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::TentBottomLeft => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(198, RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "tent_bottom_left", RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::TentBottomRight => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(181, RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "tent_bottom_right", RGB::from_u8(191, 151, 96), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::CampSeat => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(61, RGB::from_u8(145, 119, 61), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "camp_seat", RGB::from_u8(145, 119, 61), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                         },
                         TileType::Fire => {
                             let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(30, RGB::from_u8( 245, 176, 65), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::NoShading, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "fire", RGB::from_u8( 245, 176, 65), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::NoShading, ObjectShader::Background).with_layer(RenderLayer::Effects), &mut renderables)
                                     .with(Light::new(10, 1.0, RGB::from_u8( 245, 176, 65)), &mut lights)
                                     .build();
                         },
@@ -166,19 +168,19 @@ impl <'a> System<'a> for LevelGenSystem {
                             if is_front_wall {
                                 let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(223, RGB::from_u8(247, 220, 111), RGB::from_u8(243, 156, 18), ObjectShader::Foreground, ObjectShader::Foreground), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "wall_front", RGB::from_u8(247, 220, 111), RGB::from_u8(243, 156, 18), ObjectShader::Foreground, ObjectShader::Foreground), &mut renderables)
                                     .build();
                             } else {
                                 let _ = entities.build_entity()
                                     .with(Position::new(x as i32, y as i32), &mut positions)
-                                    .with(Renderable::new(219, RGB::from_u8(247, 220, 111), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                    .with(Renderable::new_from_sprite(&sprites, "wall", RGB::from_u8(247, 220, 111), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                     .build();
                             }
                         },
                          TileType::HiveFloor => {
                             let _ = entities.build_entity()
                                 .with(Position::new(x as i32, y as i32), &mut positions)
-                                .with(Renderable::new_from_char('.', RGB::from_u8(243, 156, 18), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
+                                .with(Renderable::new_from_sprite(&sprites, "floor", RGB::from_u8(243, 156, 18), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), &mut renderables)
                                 .build();
                         },
                         _ => {},
@@ -189,12 +191,11 @@ impl <'a> System<'a> for LevelGenSystem {
     }
 }
 
-fn make_grass (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, tall : bool, rng : &mut StdRng, x: usize, y: usize, distance: i32) {
+#[allow(clippy::too_many_arguments)]
+fn make_grass (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, sprites : &SpriteMap, tall : bool, rng : &mut StdRng, x: usize, y: usize, distance: i32) {
     use bracket_lib::prelude::RgbLerp;
     use rand::Rng;
 
-    let color : RGB;
-
 	// set the healthy color
 	let color_healthy : RGB;
 	if rng.gen_range(0,100) < 50 {
@@ -221,45 +222,46 @@ fn make_grass (entities: &Entities, positions: &mut WriteStorage<Position>, rend
 
 	let mut lerp = RgbLerp::new(color_healthy, color_unhealthy, 10);
 
-	if distance == 0 {
-		color = color_unhealthy;
+	let color = if distance == 0 {
+		color_unhealthy
 	} else {
 		let n = if distance >= 10 {
             9
         } else {
             distance as usize
         };
-        color = lerp.nth(n).unwrap();
-    }
+        lerp.nth(n).unwrap()
+    };
     
-    let char_id = if tall {
+    let sprite = if tall {
         if rng.gen_range(0, 100) < 70 {
-            244
+            "grass_tall"
         } else {
-            245
+            "grass_tall_bent"
         } 
     } else {
-        253
+        "grass_short"
     };
 
 	let _ = entities.build_entity()
 		.with(Position::new(x as i32, y as i32), positions)
-		.with(Renderable::new(char_id, color, RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), renderables)
+		.with(Renderable::new_from_sprite(sprites, sprite, color, RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background).with_layer(RenderLayer::Flora), renderables)
 		.build();
 }
 
-fn make_mushroom (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, lights : &mut WriteStorage<Light>, large : bool, rng : &mut StdRng, x: usize, y: usize) {
+#[allow(clippy::too_many_arguments)]
+fn make_mushroom (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, sprites : &SpriteMap, lights : &mut WriteStorage<Light>, large : bool, rng : &mut StdRng, x: usize, y: usize) {
     use rand::Rng;
     let color: RGB;
 
-    let character = if large {
+    let sprite = if large {
         if rng.gen_range(0, 100) < 80 {
-            6
+            "mushroom_large"
         } else {
-            5
+            "mushroom_large_round"
         }
     } else {
-        43
+        "mushroom_small"
     };
 
     let light_rad = if large {
@@ -280,30 +282,31 @@ fn make_mushroom (entities: &Entities, positions: &mut WriteStorage<Position>, r
 
 	let _ = entities.build_entity()
 		.with(Position::new(x as i32, y as i32), positions)
-		.with(Renderable::new(character, color, RGB::from_u8(0, 0, 0), ObjectShader::NoShading, ObjectShader::Background), renderables)
+		.with(Renderable::new_from_sprite(sprites, sprite, color, RGB::from_u8(0, 0, 0), ObjectShader::NoShading, ObjectShader::Background).with_layer(RenderLayer::Flora), renderables)
 		.with(Light::new(light_rad, 1.0, color), lights)
 		.build();
 }
 
-fn make_web (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, thick : bool, rng : &mut StdRng, x: usize, y: usize) {
-    let glyph = if thick {
-        176
+#[allow(clippy::too_many_arguments)]
+fn make_web (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, sprites : &SpriteMap, thick : bool, _rng : &mut StdRng, x: usize, y: usize) {
+    let sprite = if thick {
+        "web_thick"
     } else {
-        15
+        "web_thin"
     };
 
     let _ = entities.build_entity()
 		.with(Position::new(x as i32, y as i32), positions)
-		.with(Renderable::new(glyph, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), renderables)
+		.with(Renderable::new_from_sprite(sprites, sprite, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background).with_layer(RenderLayer::Flora), renderables)
 		.build();
 
 }
 
-fn make_egg_sac (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, x: usize, y: usize) {
-    let glyph = 7;
+fn make_egg_sac (entities: &Entities, positions: &mut WriteStorage<Position>, renderables: &mut WriteStorage<Renderable>, sprites : &SpriteMap, x: usize, y: usize) {
+    let sprite = "egg_sac";
     let _ = entities.build_entity()
 		.with(Position::new(x as i32, y as i32), positions)
-		.with(Renderable::new(glyph, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background), renderables)
+		.with(Renderable::new_from_sprite(sprites, sprite, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background).with_layer(RenderLayer::Flora), renderables)
 		.build();
 
 }
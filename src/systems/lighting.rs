@@ -5,6 +5,7 @@ use crate::components::basic::{Light, Position, Renderable};
 use lightmask::LightMask;
 use crate::level_generation::map::Map;
 use crate::systems::render::ObjectShader;
+use crate::systems::daylight::TimeOfDay;
 
 pub struct LightingSystem;
 
@@ -13,29 +14,30 @@ impl<'a> System<'a> for LightingSystem {
         ReadStorage<'a, Position>,
         WriteStorage<'a, Renderable>,
         ReadStorage<'a, Light>,
-        Read<'a, Map>
+        Read<'a, Map>,
+        Read<'a, TimeOfDay>,
     );
 
-    fn run(&mut self, (positions, mut renderables, lights, map): Self::SystemData) {
+    fn run(&mut self, (positions, mut renderables, lights, map, time_of_day): Self::SystemData) {
         use specs::Join;
 
         let mut light_mask = LightMask::new(map.width, map.height);
 
         for (position, light) in (&positions, &lights).join() {
-            light_mask.add_light(&position, &light);
+            light_mask.add_light(position, light);
         }
-        light_mask.set_ambient(map.ambient_light);
 
-        light_mask.compute_mask(&map.transparency_map);
+        light_mask.set_ambient(map.ambient_light);
+        light_mask.compute_mask(&map.transparency_map, &time_of_day.tile_light);
 
         //apply shading to renderables
         for (position, renderable) in (&positions, &mut renderables).join() {
             if !(renderable.fg_shader == ObjectShader::NoShading && renderable.bg_shader == ObjectShader::NoShading){
                 let x = position.x as usize;
                 let y = position.y as usize;
-                let r_br = light_mask.r_mask[x + y * light_mask.width] as f32;
-                let g_br = light_mask.g_mask[x + y * light_mask.width] as f32;
-                let b_br = light_mask.b_mask[x + y * light_mask.width] as f32;
+                let r_br = light_mask.r_mask[x + y * light_mask.width];
+                let g_br = light_mask.g_mask[x + y * light_mask.width];
+                let b_br = light_mask.b_mask[x + y * light_mask.width];
                 renderable.shading = RGB::from_f32(r_br, g_br, b_br);
             }
         }
@@ -85,9 +87,9 @@ pub mod lightmask {
             let x = position.x as usize;
             let y = position.y as usize;
             let rad = light.radius as f32;
-            let cost_r = -1.0 * rad * light.color.r;
-            let cost_g = -1.0 * rad * light.color.g;
-            let cost_b = -1.0 * rad * light.color.b;
+            let cost_r = -rad * light.color.r;
+            let cost_g = -rad * light.color.g;
+            let cost_b = -rad * light.color.b;
             self.distance_map_r[x + y * self.width] = cost_r;
             self.distance_map_g[x + y * self.width] = cost_g;
             self.distance_map_b[x + y * self.width] = cost_b;
@@ -100,19 +102,18 @@ pub mod lightmask {
             self.ambient_light = ambient;
         }
 
-        pub fn compute_mask (&mut self, walls : &Vec<f32>) {
+        /// Computes the light of every tile. `daylight` is added per tile on top of the ambient
+        /// light; tiles past its end get none.
+        pub fn compute_mask (&mut self, walls : &[f32], daylight : &[RGB]) {
             compute_channel(self.width, self.height, &self.lights_r, &mut self.distance_map_r, &mut self.r_mask, walls);
             compute_channel(self.width, self.height, &self.lights_g, &mut self.distance_map_g, &mut self.g_mask, walls);
             compute_channel(self.width, self.height, &self.lights_b, &mut self.distance_map_b, &mut self.b_mask, walls);
 
             for i in 0..self.r_mask.len() {
-                self.r_mask[i] += self.ambient_light.r;
-            }
-            for i in 0..self.g_mask.len() {
-                self.g_mask[i] += self.ambient_light.g;
-            }
-            for i in 0..self.b_mask.len() {
-                self.b_mask[i] += self.ambient_light.b;
+                let day = daylight.get(i).copied().unwrap_or_else(|| RGB::from_f32(0.0, 0.0, 0.0));
+                self.r_mask[i] += self.ambient_light.r + day.r;
+                self.g_mask[i] += self.ambient_light.g + day.g;
+                self.b_mask[i] += self.ambient_light.b + day.b;
             }
         } 
     }
@@ -137,19 +138,18 @@ mod lightmask_helper {
             }
         }
     }
-    pub fn compute_channel(width : usize, height : usize, lights : &Vec<Node>, distance_map : &mut Vec<f32>, mask : &mut Vec<f32>, transparency : &Vec<f32>,) {
+    pub fn compute_channel(width : usize, height : usize, lights : &[Node], distance_map : &mut [f32], mask : &mut [f32], transparency : &[f32],) {
         let mut priority_queue: Vec<Node> = Vec::new();
         
         //push all the lights to the queue
         for light_source in lights {
-            priority_queue.push(light_source.clone());
+            priority_queue.push(*light_source);
             let x = light_source.pos.0 as usize;
             let y = light_source.pos.1 as usize;
-            mask[x+y*width] = -1.0 * light_source.cost / SOME_CONSTANT;
+            mask[x+y*width] = -light_source.cost / SOME_CONSTANT;
         }
 
-        while !priority_queue.is_empty() {
-            let current_node = priority_queue.pop().unwrap();
+        while let Some(current_node) = priority_queue.pop() {
             let x = current_node.pos.0;
             let y = current_node.pos.1;
             
@@ -157,7 +157,7 @@ mod lightmask_helper {
             for dx in x - 1..x + 2 {
                 for dy in y - 1..y + 2 {
                     //make sure its in bounds and not the current node
-                    if !(dx==x && dy==y) && !(dx >= width as i32 || dx < 0) && !(dy >= height as i32 || dy < 0) {
+                    if (dx != x || dy != y) && dx >= 0 && dx < width as i32 && dy >= 0 && dy < height as i32 {
                         
                         //get distance to neighbor
                         let distance_to_neighbor = if ((x - dx).pow(2) + (y - dy).pow(2)) == 2 {
@@ -178,7 +178,7 @@ mod lightmask_helper {
                         //make sure that the calculated cost is lower
                         if calculated_cost < distance_map[dx as usize + dy as usize * width] {
                             distance_map[dx as usize + dy as usize * width] = calculated_cost;
-                            let br = -1.0 * calculated_cost / SOME_CONSTANT;
+                            let br = -calculated_cost / SOME_CONSTANT;
                             //add node to the brightness map
                             mask[dx as usize + dy as usize * width] = br;
                                 
@@ -196,3 +196,42 @@ mod lightmask_helper {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{World, WorldExt, Builder, RunNow};
+    use crate::config::Daylight;
+    use crate::level_generation::map::MapType;
+    use crate::state::Turn;
+    use crate::systems::daylight::DaylightSystem;
+
+    #[test]
+    fn exposed_tiles_in_covered_maps_get_sky_light () {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Renderable>();
+        world.register::<Light>();
+        let mut map = Map::new(5, 1, "sky".to_string(), MapType::Cavern, RGB::from_f32(0.0, 0.0, 0.0));
+        map.set_sky_exposure(0, 0, 1.0);
+        world.insert(map);
+        let noon = Daylight { start_time : 0.5, ..Daylight::default() };
+        world.insert(TimeOfDay::new(&noon));
+        world.insert(Turn(0));
+
+        let window = world.create_entity().with(Position::new(0, 0)).with(Renderable::new(46, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background)).build();
+        let covered = world.create_entity().with(Position::new(4, 0)).with(Renderable::new(46, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background)).build();
+        DaylightSystem.run_now(&world);
+        LightingSystem.run_now(&world);
+
+        let sky = world.read_resource::<TimeOfDay>().sky_light;
+        let renderables = world.read_storage::<Renderable>();
+        let window_shading = renderables.get(window).unwrap().shading;
+        let covered_shading = renderables.get(covered).unwrap().shading;
+        assert!(sky.r > 0.0);
+        assert!((window_shading.r - sky.r).abs() < 0.001, "window tile got {:?}, sky is {:?}", window_shading, sky);
+        assert_eq!(covered_shading.r, 0.0);
+        assert_eq!(covered_shading.g, 0.0);
+        assert_eq!(covered_shading.b, 0.0);
+    }
+}
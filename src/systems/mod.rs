@@ -4,4 +4,11 @@ pub mod player;
 pub mod gui;
 pub mod lighting;
 pub mod level;
-pub mod animation;
\ No newline at end of file
+pub mod animation;
+pub mod daylight;
+pub mod camera;
+pub mod spatial;
+pub mod pathing;
+pub mod sight;
+pub mod travel;
+pub mod explore;
\ No newline at end of file
@@ -0,0 +1,80 @@
+use specs::ReadStorage;
+use bracket_lib::prelude::{BaseMap, Algorithm2D, Point, SmallVec, DistanceAlg, a_star_search};
+
+use crate::components::tag::BlocksTile;
+use crate::level_generation::map::Map;
+use crate::level_generation::map::tile::is_safe;
+use crate::systems::spatial::SpatialIndex;
+
+/// View of the map used for pathing and sight. Only safe tiles that no entity blocks can be walked on.
+pub struct PathingMap<'a, 'b> {
+    map : &'a Map,
+    spatial_index : &'a SpatialIndex,
+    blockers : &'a ReadStorage<'b, BlocksTile>,
+}
+
+impl <'a, 'b> PathingMap<'a, 'b> {
+    pub fn new (map : &'a Map, spatial_index : &'a SpatialIndex, blockers : &'a ReadStorage<'b, BlocksTile>) -> Self {
+        PathingMap {
+            map : map,
+            spatial_index : spatial_index,
+            blockers : blockers,
+        }
+    }
+
+    pub fn is_walkable (&self, position : (i32, i32)) -> bool {
+        if !self.in_bounds(Point::from_tuple(position)) {
+            return false;
+        }
+        let tile = self.map.tiles[position.0 as usize + position.1 as usize * self.map.width];
+        return is_safe(tile) && !self.spatial_index.is_blocked(position, self.blockers);
+    }
+}
+
+impl <'a, 'b> BaseMap for PathingMap<'a, 'b> {
+    fn is_opaque (&self, idx : usize) -> bool {
+        return self.map.transparency_map[idx] >= 1.0;
+    }
+
+    fn get_available_exits (&self, idx : usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let position = self.index_to_point2d(idx);
+        for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0), (-1, -1), (1, -1), (-1, 1), (1, 1)].iter() {
+            let next = (position.x + dx, position.y + dy);
+            if self.is_walkable(next) {
+                let cost = if *dx != 0 && *dy != 0 { 1.45 } else { 1.0 };
+                exits.push((self.point2d_to_index(Point::from_tuple(next)), cost));
+            }
+        }
+        return exits;
+    }
+
+    fn get_pathing_distance (&self, idx1 : usize, idx2 : usize) -> f32 {
+        return DistanceAlg::Pythagoras.distance2d(self.index_to_point2d(idx1), self.index_to_point2d(idx2));
+    }
+}
+
+impl <'a, 'b> Algorithm2D for PathingMap<'a, 'b> {
+    fn dimensions (&self) -> Point {
+        return Point::new(self.map.width, self.map.height);
+    }
+}
+
+/// Finds a path between two tiles with A*. The path excludes the start and includes the end.
+pub fn find_path (map : &Map, spatial_index : &SpatialIndex, blockers : &ReadStorage<BlocksTile>, start : (i32, i32), end : (i32, i32)) -> Option<Vec<(i32, i32)>> {
+    let pathing_map = PathingMap::new(map, spatial_index, blockers);
+    if !pathing_map.in_bounds(Point::from_tuple(start)) || !pathing_map.is_walkable(end) {
+        return Option::None;
+    }
+    let start_idx = pathing_map.point2d_to_index(Point::from_tuple(start));
+    let end_idx = pathing_map.point2d_to_index(Point::from_tuple(end));
+    let path = a_star_search(start_idx, end_idx, &pathing_map);
+    if !path.success {
+        return Option::None;
+    }
+    let steps : Vec<(i32, i32)> = path.steps.iter().skip(1).map(|idx| {
+        let point = pathing_map.index_to_point2d(*idx);
+        (point.x, point.y)
+    }).collect();
+    return Option::from(steps);
+}
@@ -1,9 +1,13 @@
-use specs::{System, ReadStorage, WriteStorage, Read, Entities};
-use bracket_lib::prelude::VirtualKeyCode;
+use specs::{System, ReadStorage, WriteStorage, Read, Write, Entities};
 use crate::components::basic::*;
 use crate::components::tag::PlayerTag;
-use crate::state::CurrentInput;
-use crate::raw::*;
+use crate::state::{CurrentInput, Turn};
+use crate::systems::spatial::SpatialIndex;
+use crate::game_log::{GameLog, LogCategory};
+use crate::input::Command;
+use crate::systems::explore::Exploration;
+use crate::raw::{SharedRaws, ItemType};
+use crate::locale::SharedStrings;
 
 pub struct PickUpSystem;
 
@@ -15,78 +19,85 @@ impl <'a> System <'a> for PickUpSystem {
         ReadStorage <'a, Currency>,
         WriteStorage <'a, Inventory>,
         Read <'a, CurrentInput>,
+        Write <'a, Turn>,
+        Read <'a, SpatialIndex>,
+        Write <'a, GameLog>,
+        Write <'a, Exploration>,
+        Read <'a, SharedRaws>,
+        Read <'a, SharedStrings>,
         Entities<'a>
     );
 
-    fn run (&mut self, (positions, item_wrappers, player_tag, currencies, mut inventory, current_input, entities) : Self::SystemData) {
+    fn run (&mut self, (positions, item_wrappers, player_tag, currencies, mut inventory, current_input, mut turn, spatial_index, mut game_log, mut exploration, raws, strings, entities) : Self::SystemData) {
         use specs::Join;
 
-        match current_input.key {
-            Some(VirtualKeyCode::G) => {
-                
-                //get player coords
-                let mut player_x = 0;
-                let mut player_y = 0;
-                for (_player, position) in (&player_tag, &positions).join() {
-                    player_x = position.x;
-                    player_y = position.y;
-                }
+        // auto-explore asks for items to be picked up as it passes them
+        let requested = exploration.pickup_requested;
+        exploration.pickup_requested = false;
 
-                //try to get the item data
-                let mut item_data : Option<ItemRaw> = Option::None;
-                let mut currency_amt = 0;
-                for (position, item_wrapper, e) in (&positions, &item_wrappers, &entities).join() {
-                    if player_x == position.x && player_y == position.y {
-                        //get the item wrapper data
-                        item_data = Option::from(item_wrapper.item_data.clone());
-                        
-                        //if it's a currency get its amount too
-                        let value_option = currencies.get(e);
-                        match value_option {
-                            Some(currency) => {
-                                currency_amt = currency.amt;
-                            },
-                            None => {}
-                        }
+        if requested || current_input.command == Option::from(Command::PickUp) {
+            
+            //get player coords
+            let mut player_x = 0;
+            let mut player_y = 0;
+            for (_player, position) in (&player_tag, &positions).join() {
+                player_x = position.x;
+                player_y = position.y;
+            }
 
-                        // delete the entity
-                        let delete_result = entities.delete(e);
-                        match delete_result {
-                            Ok(_t) => {
-                                info!("Picked up item \"{}\" at position {},{}", item_wrapper.item_data.name, position.x, position.y);
-                            },
-                            Err(e) => {
-                                error!("{}", e);
-                                error!("Error deleting item \"{}\" entity on pick up at postion {}, {}", item_wrapper.item_data.name, position.x, position.y);
-                            },
-                        }
-                    }
-                }
+            //pick up every item on the player's tile
+            let mut picked_up = false;
+            for e in spatial_index.entities_at((player_x, player_y)) {
+                let item_wrapper = match item_wrappers.get(*e) {
+                    Some(item_wrapper) => item_wrapper,
+                    None => continue,
+                };
+                let item_raw = item_wrapper.item_data.clone();
+                let item_name = strings.item_name(&item_raw);
 
-                //if there is item data do something with it
-                match item_data {
-                    Some(item_raw) => {
-                        // if the item is a currency add it to the total currency, else add it to the wallet
-                        match item_raw.item_type.as_str() {
-                            "currency" => {
-                                 for (_player, inventory) in (&player_tag, &mut inventory).join() {
-                                    inventory.money += item_raw.value * currency_amt as f32;
-                                }
-                            },
-                            _ => {
-                                 for (_player, inventory) in (&player_tag, &mut inventory).join() {
-                                    inventory.add_item(item_raw.clone());
-                                }
-                            },
+                // if the item is a currency add it to the total currency, else add it to the inventory
+                match item_raw.item_type {
+                    ItemType::Currency => {
+                        let currency_amt = match currencies.get(*e) {
+                            Some(currency) => currency.amt,
+                            None => 0,
+                        };
+                        for (_player, inventory) in (&player_tag, &mut inventory).join() {
+                            inventory.money += item_raw.value * currency_amt as f32;
+                        }
+                        game_log.push_counted(LogCategory::Pickup, "log.pick_up", &item_name, currency_amt, turn.0, |count| strings.format("log.pick_up", &[&count, &item_name]));
+                    },
+                    _ => {
+                        let mut added = false;
+                        for (_player, inventory) in (&player_tag, &mut inventory).join() {
+                            added = inventory.add_item(&raws, item_raw.clone());
                         }
+                        // leave items that aren't in the raws where they are
+                        if !added {
+                            continue;
+                        }
+                        game_log.push_counted(LogCategory::Pickup, "log.pick_up", &item_name, 1, turn.0, |count| strings.format("log.pick_up", &[&count, &item_name]));
+                    },
+                }
 
-                       
+                // delete the entity
+                let delete_result = entities.delete(*e);
+                match delete_result {
+                    Ok(_t) => {
+                        info!("Picked up item \"{}\" at position {},{}", item_raw.id, player_x, player_y);
+                    },
+                    Err(err) => {
+                        error!("{}", err);
+                        error!("Error deleting item \"{}\" entity on pick up at postion {}, {}", item_raw.id, player_x, player_y);
                     },
-                    None => {},
                 }
-            },
-            _ => {},
+                picked_up = true;
+            }
+
+            if picked_up {
+                turn.0 += 1;
+            }
         }
         
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
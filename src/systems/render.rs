@@ -1,4 +1,4 @@
-use specs::{System, ReadStorage};
+use specs::{System, ReadStorage, Read};
 use bracket_lib::prelude::DrawBatch;
 use bracket_lib::prelude::Point;
 use bracket_lib::prelude::ColorPair;
@@ -6,19 +6,42 @@ use bracket_lib::prelude::RGB;
 use bracket_lib::prelude::Rect;
 use object_pool::Reusable;
 
-use crate::components::basic::{Position, Renderable, Inventory, Actor};
-use crate::components::gui::{PlayerCard, Justification, Panel};
+use crate::components::basic::{Renderable, Inventory, Actor, ItemWrapper};
+use crate::components::gui::{PlayerCard, MessageLogCard, Justification, Panel};
 use crate::components::tag::PlayerTag;
-use crate::raw::RAW;
+use crate::level_generation::map::Map;
+use crate::level_generation::map::tile::get_tile_name;
+use crate::systems::camera::{Camera, CameraMode};
+use crate::systems::spatial::SpatialIndex;
+use crate::game_log::GameLog;
+use crate::state::CurrentInput;
+use crate::widgets::{Label, ProgressBar, ScrollView, draw_frame};
+use crate::locale::{SharedStrings, Strings};
+use crate::input::{Command, KeyBindings};
+
+/// Appends the keys bound to a command to a label, e.g. "Items: 3 (I)".
+fn with_key_hint (text : String, key_bindings : &KeyBindings, command : Command) -> String {
+    match key_bindings.describe(command) {
+        Some(keys) => return format!("{} ({})", text, keys),
+        None => return text,
+    }
+}
 
-// ///Returns true if the given screen coords are on screen.
-// fn on_screen (screen_coords:(i32,i32), screen_size: (i32,i32)) -> bool {
-// 	let mut renderable = true;
-// 	if ((screen_coords.0 < 0) || (screen_coords.0 > screen_size.0)) || ((screen_coords.1 < 0) || (screen_coords.1 > screen_size.1)) {
-// 		renderable = false;
-// 	}
-// 	return renderable;
-// }
+/// Returns the name of the tile at the given map position and the names of the things on it.
+fn describe_tile (position : (i32, i32), map : &Map, spatial_index : &SpatialIndex, player_tag : &ReadStorage<PlayerTag>, item_wrappers : &ReadStorage<ItemWrapper>, strings : &Strings) -> (String, Vec<String>) {
+    let tile_name = strings.tile_name(get_tile_name(map.tiles[position.0 as usize + position.1 as usize * map.width]));
+    let mut things : Vec<String> = Vec::new();
+    for entity in spatial_index.entities_at(position) {
+        if player_tag.contains(*entity) {
+            things.push(strings.get("gui.you").to_string());
+        }
+        match item_wrappers.get(*entity) {
+            Some(item_wrapper) => things.push(strings.item_name(&item_wrapper.item_data)),
+            None => {},
+        }
+    }
+    return (tile_name, things);
+}
 
 #[derive(Debug, PartialEq)]
 pub enum ObjectShader {
@@ -27,10 +50,19 @@ pub enum ObjectShader {
     Background,
 }
 
+/// Draw order of renderables sharing a tile. Later layers are drawn over earlier ones.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum RenderLayer {
+    Ground,
+    Flora,
+    Items,
+    Actors,
+    Effects,
+    Player,
+}
+
 pub struct RenderSystem {
     pub draw_batch : Reusable<'static, DrawBatch>,
-    pub horiz_offset : i32,
-    pub vert_offset : i32,
     pub screen_size : (u32, u32),
 }
 
@@ -38,8 +70,6 @@ impl RenderSystem {
     pub fn new (draw_batch : Reusable<'static, DrawBatch>, screen_size : (u32, u32)) -> Self {
         RenderSystem {
             draw_batch : draw_batch,
-            horiz_offset : -(screen_size.0 as i32/8),
-            vert_offset : 0,
             screen_size : screen_size,
         }
     }
@@ -47,82 +77,57 @@ impl RenderSystem {
 
 impl <'a> System<'a> for RenderSystem {
     type SystemData = (
-        ReadStorage <'a, Position>,
         ReadStorage <'a, Renderable>,
-        ReadStorage <'a, PlayerTag>,
-        ReadStorage <'a, Inventory>,
-        ReadStorage <'a, Actor>,
-        ReadStorage <'a, PlayerCard>,
+        Read <'a, Camera>,
+        Read <'a, SpatialIndex>,
     );
 
-    fn run (&mut self, (positions, renderables, player_tag, inventory, actors, player_card) : Self::SystemData) {
-        use specs::Join;
+    fn run (&mut self, (renderables, camera, spatial_index) : Self::SystemData) {
+        //the text console is only used by other states; clear whatever they left behind
+        self.draw_batch.target(1);
+        self.draw_batch.cls();
         self.draw_batch.target(0);
         self.draw_batch.cls();
 
-        //get offset from gui
-        for card in (&player_card).join() {
-            self.horiz_offset = match card.justification {
-                Justification::RIGHT => -(self.screen_size.0 as i32/8),
-                Justification::LEFT => (self.screen_size.0 as i32/8),
-                _ => 0,
-            }
-        }
-
-        //get player coords
-        let mut player_x = 0;
-        let mut player_y = 0;
-        for (position, _player) in (&positions, &player_tag).join() {
-            player_x = position.x;
-            player_y = position.y;
-        }  
-        
-        //draw all non actors first
-        for (position, renderable, _actor, _player) in (&positions, &renderables, !&actors, !&player_tag).join() {
-            let screen_x = {(self.screen_size.0 as i32 / 2) + (position.x - player_x) + self.horiz_offset};
-            let screen_y = { (self.screen_size.1 as i32 /2) + (position.y - player_y) + self.vert_offset};
-
-            let fg = renderable.get_shaded_foreground();
-            let bg = renderable.get_shaded_background();
-            self.draw_batch.set(Point::new(screen_x, screen_y), ColorPair::new(fg, bg), renderable.glyph);
-
-        }
-        //draw non player actors
-        for (position, renderable, _player) in (&positions, &renderables, !&player_tag).join() {
-            let screen_x = {(self.screen_size.0 as i32 / 2) + (position.x - player_x) + self.horiz_offset};
-		    let screen_y = { (self.screen_size.1 as i32 /2) + (position.y - player_y) + self.vert_offset};
-            
-            let fg = renderable.get_shaded_foreground();
-            let bg = renderable.get_shaded_background();
-            self.draw_batch.set(Point::new(screen_x, screen_y), ColorPair::new(fg, bg), renderable.glyph);
-        }
-
-        //draw player
-        for (position, _player, renderable) in (&positions, &player_tag, &renderables).join() {
-            let screen_x = {(self.screen_size.0 as i32 / 2) + (position.x - player_x) + self.horiz_offset};
-            let screen_y = { (self.screen_size.1 as i32 /2) + (position.y - player_y) + self.vert_offset};
-            
-            let fg = renderable.get_shaded_foreground();
-            let bg = renderable.get_shaded_background();
-            self.draw_batch.set(Point::new(screen_x, screen_y), ColorPair::new(fg, bg), renderable.glyph);
-        }
+        //draw only the tiles in the viewport, and only the top layer of each tile
+        let viewport = camera.viewport;
+        for screen_y in viewport.y1..viewport.y2 {
+            for screen_x in viewport.x1..viewport.x2 {
+                let tile = camera.screen_to_map((screen_x, screen_y));
+
+                let mut top : Option<&Renderable> = Option::None;
+                for entity in spatial_index.entities_at(tile) {
+                    match renderables.get(*entity) {
+                        Some(renderable) => {
+                            let is_above = match top {
+                                Some(current) => renderable.layer >= current.layer,
+                                None => true,
+                            };
+                            if is_above {
+                                top = Option::from(renderable);
+                            }
+                        },
+                        None => {},
+                    }
+                }
 
-        //draw inventory (temporary)
-        for (_player, invent) in (&player_tag, &inventory).join() {
-            self.draw_batch.print(Point::new(0, 1), "Inventory:123456789");
-            self.draw_batch.print(Point::new(0,2), format!("Gold: ${:.2}", invent.money));
-            if invent.get_size() < 1 {
-                self.draw_batch.print(Point::new(0, 3), "Empty :(");
-            } else {
-                let mut i = 0;
-                for (item_id, amt) in invent.items.iter() {
-                    let item_name = RAW.lock().unwrap().get_item_name(*item_id);
-                    self.draw_batch.print(Point::new(0, 3 + i), format!("{} x{}", item_name, amt));
-                    i += 1;
+                match top {
+                    Some(renderable) => {
+                        let fg = renderable.get_shaded_foreground();
+                        let bg = renderable.get_shaded_background();
+                        self.draw_batch.set(Point::new(screen_x, screen_y), ColorPair::new(fg, bg), renderable.glyph);
+                    },
+                    None => {},
                 }
             }
         }
 
+        //highlight the look cursor
+        if camera.mode == CameraMode::Look && camera.in_view(camera.cursor) {
+            let screen = camera.map_to_screen(camera.cursor);
+            self.draw_batch.set_bg(Point::from_tuple(screen), RGB::from_u8(200, 200, 0));
+        }
+
         let draw_result = self.draw_batch.submit(0);
         match draw_result {
             Ok(_v) => {},
@@ -145,77 +150,97 @@ impl GUIRenderSystem {
             screen_size : screen_size,
         }
     }
-
-    pub fn draw_bar_horizontal (&mut self, position : (i32,i32), width : i32, current : i32, max : i32, fg : RGB, bg : RGB, with_decoration : bool) {
-        let percent = current as f32 / max as f32;
-        let fill_width = (percent * width as f32) as i32;
-
-        let fill_width_float : f32 = percent * width as f32;
-        let mut offset = 0;
-        if with_decoration {
-            offset = 1;
-            self.draw_batch.set(Point::from((position.0,position.1)), ColorPair::new(RGB::named(bracket_lib::prelude::WHITE), RGB::named(bracket_lib::prelude::BLACK)), 180);
-        }
-
-        for x in 0..(width + offset) {
-            let difference = fill_width_float - x as f32;
-            if (x == 0 && x < fill_width) || (x > 0 && x <= fill_width) || (x == 0 && difference > 0.0) {
-                if difference >= 1.0 { // 100%
-                    self.draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 219);
-                } else if difference < 1.00 && difference > 0.50 { // <100%
-                    self.draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 178);
-                } else if difference <= 0.50 && difference > 0.25 { // <50%
-                    self.draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 177);
-                } else if difference <= 0.25 && difference > 0.0 { // <25%
-                    self.draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 176);
-                }
-            } else {
-               self.draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 249);
-            }
-        }
-
-        if with_decoration {
-            self.draw_batch.set(Point::from((position.0 + (width + offset), position.1)), ColorPair::new(RGB::named(bracket_lib::prelude::WHITE), bg), 195);
-        }
-
-    }
 }
 
 impl <'a> System<'a> for GUIRenderSystem {
     type SystemData = (
         ReadStorage <'a, PlayerTag>,
         ReadStorage <'a, Actor>,
+        ReadStorage <'a, Inventory>,
         ReadStorage <'a, PlayerCard>,
-        ReadStorage <'a, Panel>
+        ReadStorage <'a, MessageLogCard>,
+        ReadStorage <'a, Panel>,
+        ReadStorage <'a, ItemWrapper>,
+        Read <'a, Map>,
+        Read <'a, Camera>,
+        Read <'a, SpatialIndex>,
+        Read <'a, GameLog>,
+        Read <'a, CurrentInput>,
+        Read <'a, SharedStrings>,
+        Read <'a, KeyBindings>,
     );
 
-    fn run (&mut self, (player_tag, actors, player_card, panels) : Self::SystemData) {
+    fn run (&mut self, (player_tag, actors, inventories, player_card, log_cards, panels, item_wrappers, map, camera, spatial_index, game_log, current_input, strings, key_bindings) : Self::SystemData) {
         use specs::Join;
         self.draw_batch.target(0);
 
-        for (card, panel) in (&player_card, &panels).join() {
-            let mut enabled = true;
-            let mut x_coord = 0;
-            match card.justification {
-                Justification::RIGHT => x_coord = self.screen_size.0 - (panel.width()+1) as u32,
-                Justification::LEFT => x_coord = 0,
-                _ => enabled = false,
-            }
+        for (_card, panel) in (&player_card, &panels).join() {
+            let enabled = panel.justification == Justification::RIGHT || panel.justification == Justification::LEFT;
             if enabled {
-                self.draw_batch.draw_double_box(Rect::with_size(x_coord, 0, panel.width() as u32, self.screen_size.1-1), ColorPair::new(RGB::from_u8(255, 255, 255), RGB::from_u8(50, 50, 50)));
-                //draw player stats (temporary)
+                draw_frame(&mut self.draw_batch, panel, true);
+                //draw player stats
                 for (_player, player_actor) in (&player_tag, &actors).join() {
-                    self.draw_batch.print(Point::new(x_coord+1, 1), format!("STR:{}", player_actor.strength));
-                    self.draw_batch.print(Point::new(x_coord+1, 2), format!("DEX:{}", player_actor.dexterity));
-                    self.draw_batch.print(Point::new(x_coord+1, 3), format!("CON:{}", player_actor.constitution));
-                    self.draw_batch.print(Point::new(x_coord+1, 4), format!("WIS:{}", player_actor.wisdom));
-                    self.draw_batch.print(Point::new(x_coord+1, 5), format!("INT:{}", player_actor.intelligence));
-                    let hp_info = format!("HP:{}/{}", player_actor.current_health, player_actor.max_health);
-                    self.draw_bar_horizontal((0,0), 10, player_actor.current_health, player_actor.max_health, RGB::from_u8(255, 0, 0), RGB::from_u8(0, 0, 0) , true);
-                    self.draw_batch.print(Point::new(x_coord+1, 6), hp_info.clone());       
+                    Label::new(strings.format("gui.strength", &[&player_actor.strength])).draw(&mut self.draw_batch, &panel.row(0));
+                    Label::new(strings.format("gui.dexterity", &[&player_actor.dexterity])).draw(&mut self.draw_batch, &panel.row(1));
+                    Label::new(strings.format("gui.constitution", &[&player_actor.constitution])).draw(&mut self.draw_batch, &panel.row(2));
+                    Label::new(strings.format("gui.wisdom", &[&player_actor.wisdom])).draw(&mut self.draw_batch, &panel.row(3));
+                    Label::new(strings.format("gui.intelligence", &[&player_actor.intelligence])).draw(&mut self.draw_batch, &panel.row(4));
+                    Label::new(strings.format("gui.health", &[&player_actor.current_health, &player_actor.max_health])).draw(&mut self.draw_batch, &panel.row(5));
+                    ProgressBar::new("", player_actor.current_health, player_actor.max_health, RGB::from_u8(255, 0, 0), RGB::from_u8(0, 0, 0)).draw(&mut self.draw_batch, &panel.row(6));
                 }
+                for (_player, inventory) in (&player_tag, &inventories).join() {
+                    Label::new(strings.format("gui.gold", &[&format!("{:.2}", inventory.money)])).draw(&mut self.draw_batch, &panel.row(8));
+                    Label::new(with_key_hint(strings.format("gui.items", &[&inventory.get_size()]), &key_bindings, Command::OpenInventory)).draw(&mut self.draw_batch, &panel.row(9));
+                }
+                Label::new(with_key_hint(strings.format("gui.seed", &[&map.seed_code()]), &key_bindings, Command::CopySeed)).draw(&mut self.draw_batch, &panel.row(11));
+            }
+        }
+
+        //draw the message log
+        for (log_card, panel) in (&log_cards, &panels).join() {
+            draw_frame(&mut self.draw_batch, panel, false);
+            if log_card.scroll > 0 {
+                self.draw_batch.print(Point::new(panel.bounds.x1 + 2, panel.bounds.y1), strings.format("gui.newer", &[&log_card.scroll]));
+            }
+
+            let lines = game_log.entries.iter().map(|entry| (entry.display_text(), entry.color())).collect();
+            let area = panel.child(Rect::with_size(0, 0, panel.content().width(), panel.content().height()));
+            ScrollView::new(lines).anchored_bottom().with_scroll(log_card.scroll).draw(&mut self.draw_batch, &area);
+        }
+
+        //describe whatever is under the look cursor
+        if camera.mode == CameraMode::Look {
+            let (tile_name, things) = describe_tile(camera.cursor, &map, &spatial_index, &player_tag, &item_wrappers, &strings);
+
+            let bounds = Rect::with_size(camera.viewport.x1, camera.viewport.y2 - 4, camera.viewport.width() - 1, 3);
+            self.draw_batch.draw_box(bounds, ColorPair::new(RGB::from_u8(255, 255, 255), RGB::from_u8(50, 50, 50)));
+            self.draw_batch.print(Point::new(bounds.x1 + 1, bounds.y1 + 1), strings.format("gui.looking_at", &[&tile_name]));
+            if things.is_empty() {
+                self.draw_batch.print(Point::new(bounds.x1 + 1, bounds.y1 + 2), strings.get("gui.nothing_of_interest"));
+            } else {
+                self.draw_batch.print(Point::new(bounds.x1 + 1, bounds.y1 + 2), things.join(", "));
+            }
+        }
+
+        //tooltip for the tile under the mouse
+        let mouse = current_input.mouse_pos;
+        let hovered = camera.screen_to_map(mouse);
+        let on_map = hovered.0 >= 0 && hovered.1 >= 0 && hovered.0 < map.width as i32 && hovered.1 < map.height as i32;
+        if camera.mode == CameraMode::Follow && on_map && camera.viewport.point_in_rect(Point::from_tuple(mouse)) {
+            let (tile_name, things) = describe_tile(hovered, &map, &spatial_index, &player_tag, &item_wrappers, &strings);
+            let mut lines = vec![tile_name];
+            lines.extend(things);
+            let width = lines.iter().map(|line| line.chars().count() as i32).max().unwrap_or(0) + 1;
+            let height = lines.len() as i32 + 1;
+
+            //keep the tooltip beside the cursor and inside the viewport
+            let x = if mouse.0 + width + 2 < camera.viewport.x2 { mouse.0 + 1 } else { (mouse.0 - width - 1).max(camera.viewport.x1) };
+            let y = mouse.1.min(camera.viewport.y2 - height - 1).max(camera.viewport.y1);
+            let bounds = Rect::with_size(x, y, width, height);
+            self.draw_batch.draw_box(bounds, ColorPair::new(RGB::from_u8(255, 255, 255), RGB::from_u8(50, 50, 50)));
+            for (idx, line) in lines.iter().enumerate() {
+                self.draw_batch.print(Point::new(bounds.x1 + 1, bounds.y1 + 1 + idx as i32), line);
             }
-            
         }
 
         let draw_result = self.draw_batch.submit(1);
@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+use specs::{System, Read, Write, ReadStorage};
+use bracket_lib::prelude::{Point, field_of_view};
+
+use crate::components::basic::Position;
+use crate::components::tag::{PlayerTag, BlocksTile};
+use crate::level_generation::map::Map;
+use crate::systems::spatial::SpatialIndex;
+use crate::systems::pathing::PathingMap;
+
+/// How far the player can see.
+pub const SIGHT_RADIUS : i32 = 10;
+
+/// Tiles the player can see, worked out once per update tick.
+#[derive(Default)]
+pub struct Visibility {
+    /// Visible tiles in the order field of view found them.
+    pub tiles : Vec<(i32, i32)>,
+    lookup : HashSet<(i32, i32)>,
+}
+
+impl Visibility {
+    pub fn is_visible (&self, position : (i32, i32)) -> bool {
+        return self.lookup.contains(&position);
+    }
+}
+
+/// Updates `Visibility` from the player's position.
+pub struct SightSystem;
+
+impl <'a> System<'a> for SightSystem {
+    type SystemData = (
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, BlocksTile>,
+        Read<'a, Map>,
+        Read<'a, SpatialIndex>,
+        Write<'a, Visibility>,
+    );
+
+    fn run (&mut self, (player_tag, positions, blockers, map, spatial_index, mut visibility) : Self::SystemData) {
+        use specs::Join;
+
+        visibility.tiles.clear();
+        visibility.lookup.clear();
+        for (_player, position) in (&player_tag, &positions).join() {
+            let sight = PathingMap::new(&map, &spatial_index, &blockers);
+            for point in field_of_view(Point::new(position.x, position.y), SIGHT_RADIUS, &sight) {
+                visibility.tiles.push((point.x, point.y));
+                visibility.lookup.insert((point.x, point.y));
+            }
+        }
+    }
+}
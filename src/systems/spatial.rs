@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use specs::prelude::{System, SystemData, World, WorldExt, ReadStorage, Read, Write, Entities, Entity, ReaderId, ComponentEvent};
+use specs::world::Index;
+
+use crate::components::basic::Position;
+use crate::components::tag::BlocksTile;
+use crate::level_generation::map::Map;
+
+/// Buckets entities by the tile they stand on.
+///
+/// The index is brought up to date once per update tick, so entities deleted with `entities.delete`
+/// stay listed until the world is maintained and the index has run again. Look them up in a storage
+/// before use; storages stop returning components for them as soon as they are maintained.
+#[derive(Default)]
+pub struct SpatialIndex {
+    pub width : usize,
+    pub height : usize,
+    tiles : Vec<Vec<Entity>>,
+    /// Tile index each entity is currently filed under, keyed by entity id.
+    locations : HashMap<Index, usize>,
+}
+
+impl SpatialIndex {
+    pub fn new (width : usize, height : usize) -> Self {
+        SpatialIndex {
+            width : width,
+            height : height,
+            tiles : vec![Vec::new(); width * height],
+            locations : HashMap::new(),
+        }
+    }
+
+    /// Files an entity under the given tile, moving it if it was already indexed elsewhere.
+    /// Entities outside the map are dropped from the index.
+    pub fn insert (&mut self, entity : Entity, position : (i32, i32)) {
+        self.remove(entity.id());
+        match self.tile_index(position) {
+            Some(idx) => {
+                self.tiles[idx].push(entity);
+                self.locations.insert(entity.id(), idx);
+            },
+            None => {},
+        }
+    }
+
+    /// Drops an entity from the index.
+    pub fn remove (&mut self, id : Index) {
+        match self.locations.remove(&id) {
+            Some(idx) => {
+                self.tiles[idx].retain(|e| e.id() != id);
+            },
+            None => {},
+        }
+    }
+
+    /// Returns all entities on the given tile.
+    pub fn entities_at (&self, position : (i32, i32)) -> &[Entity] {
+        match self.tile_index(position) {
+            Some(idx) => &self.tiles[idx],
+            None => &[],
+        }
+    }
+
+    /// Returns all entities within `radius` tiles of the given tile, including the tile itself.
+    pub fn entities_in_radius (&self, center : (i32, i32), radius : i32) -> Vec<Entity> {
+        let mut found = Vec::new();
+        for y in (center.1 - radius)..(center.1 + radius + 1) {
+            for x in (center.0 - radius)..(center.0 + radius + 1) {
+                let dx = x - center.0;
+                let dy = y - center.1;
+                if dx * dx + dy * dy <= radius * radius {
+                    found.extend_from_slice(self.entities_at((x, y)));
+                }
+            }
+        }
+        return found;
+    }
+
+    /// Returns the entity blocking the given tile, if there is one.
+    pub fn blocking_entity_at (&self, position : (i32, i32), blockers : &ReadStorage<BlocksTile>) -> Option<Entity> {
+        for entity in self.entities_at(position) {
+            if blockers.contains(*entity) {
+                return Option::from(*entity);
+            }
+        }
+        return Option::None;
+    }
+
+    /// Returns true if an entity blocks the given tile.
+    pub fn is_blocked (&self, position : (i32, i32), blockers : &ReadStorage<BlocksTile>) -> bool {
+        return self.blocking_entity_at(position, blockers).is_some();
+    }
+
+    fn tile_index (&self, position : (i32, i32)) -> Option<usize> {
+        if position.0 < 0 || position.1 < 0 || position.0 >= self.width as i32 || position.1 >= self.height as i32 {
+            return Option::None;
+        }
+        return Option::from(position.0 as usize + position.1 as usize * self.width);
+    }
+}
+
+/// Keeps the `SpatialIndex` in sync with the `Position` storage.
+#[derive(Default)]
+pub struct SpatialIndexSystem {
+    reader : Option<ReaderId<ComponentEvent>>,
+}
+
+impl <'a> System<'a> for SpatialIndexSystem {
+    type SystemData = (
+        ReadStorage<'a, Position>,
+        Read<'a, Map>,
+        Write<'a, SpatialIndex>,
+        Entities<'a>,
+    );
+
+    fn setup (&mut self, world : &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Option::from(world.write_storage::<Position>().register_reader());
+    }
+
+    fn run (&mut self, (positions, map, mut index, entities) : Self::SystemData) {
+        use specs::Join;
+
+        // a new map means starting over
+        if index.width != map.width || index.height != map.height {
+            *index = SpatialIndex::new(map.width, map.height);
+            for (entity, position) in (&entities, &positions).join() {
+                index.insert(entity, (position.x, position.y));
+            }
+        }
+
+        let reader = self.reader.as_mut().expect("SpatialIndexSystem was not set up");
+        for event in positions.channel().read(reader) {
+            match event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => {
+                    let entity = entities.entity(*id);
+                    match positions.get(entity) {
+                        Some(position) => index.insert(entity, (position.x, position.y)),
+                        None => index.remove(*id),
+                    }
+                },
+                ComponentEvent::Removed(id) => {
+                    index.remove(*id);
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::{Builder, RunNow};
+    use bracket_lib::prelude::RGB;
+    use crate::level_generation::map::MapType;
+
+    fn world () -> (World, SpatialIndexSystem) {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<BlocksTile>();
+        world.insert(Map::new(10, 10, "spatial".to_string(), MapType::Empty, RGB::from_f32(0.0, 0.0, 0.0)));
+        let mut system = SpatialIndexSystem::default();
+        System::setup(&mut system, &mut world);
+        return (world, system);
+    }
+
+    #[test]
+    fn moved_entities_are_refiled () {
+        let (mut world, mut system) = world();
+        let blocker = world.create_entity().with(Position::new(2, 2)).with(BlocksTile).build();
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<SpatialIndex>().entities_at((2, 2)), &[blocker]);
+
+        world.write_storage::<Position>().get_mut(blocker).unwrap().x = 7;
+        system.run_now(&world);
+
+        let index = world.read_resource::<SpatialIndex>();
+        let blockers = world.read_storage::<BlocksTile>();
+        assert!(index.entities_at((2, 2)).is_empty());
+        assert_eq!(index.entities_at((7, 2)), &[blocker]);
+        assert!(index.entities_in_radius((2, 2), 2).is_empty());
+        assert_eq!(index.entities_in_radius((6, 2), 1), vec![blocker]);
+        assert_eq!(index.blocking_entity_at((2, 2), &blockers), Option::None);
+        assert_eq!(index.blocking_entity_at((7, 2), &blockers), Option::from(blocker));
+    }
+
+    #[test]
+    fn deleted_entities_are_dropped () {
+        let (mut world, mut system) = world();
+        let blocker = world.create_entity().with(Position::new(4, 4)).with(BlocksTile).build();
+        let other = world.create_entity().with(Position::new(5, 4)).build();
+        system.run_now(&world);
+        assert_eq!(world.read_resource::<SpatialIndex>().entities_in_radius((4, 4), 1).len(), 2);
+
+        world.entities().delete(blocker).unwrap();
+        world.maintain();
+        system.run_now(&world);
+
+        let index = world.read_resource::<SpatialIndex>();
+        let blockers = world.read_storage::<BlocksTile>();
+        assert!(index.entities_at((4, 4)).is_empty());
+        assert_eq!(index.entities_in_radius((4, 4), 1), vec![other]);
+        assert_eq!(index.blocking_entity_at((4, 4), &blockers), Option::None);
+        assert!(!index.is_blocked((4, 4), &blockers));
+    }
+}
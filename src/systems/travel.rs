@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use specs::{System, Read, Write, ReadStorage, WriteStorage, Entity};
+use bracket_lib::prelude::Point;
+
+use crate::components::basic::Position;
+use crate::components::tag::{PlayerTag, BlocksTile, Hostile};
+use crate::level_generation::map::Map;
+use crate::state::{CurrentInput, DeltaTime, Turn};
+use crate::systems::camera::{Camera, CameraMode};
+use crate::systems::spatial::SpatialIndex;
+use crate::systems::pathing::find_path;
+use crate::systems::sight::{Visibility, SIGHT_RADIUS};
+use crate::game_log::{GameLog, LogCategory};
+use crate::locale::SharedStrings;
+
+/// Milliseconds between steps while travelling, so the walk can be followed on screen.
+pub const STEP_DELAY : f32 = 60.0;
+
+/// The route the player is automatically walking along.
+#[derive(Default)]
+pub struct Travel {
+    /// Tiles left to walk, next step first.
+    pub path : VecDeque<(i32, i32)>,
+    /// Hostiles that were already in view when travel started; only new ones interrupt it.
+    known_hostiles : Vec<Entity>,
+    since_last_step : f32,
+}
+
+impl Travel {
+    pub fn is_travelling (&self) -> bool {
+        return !self.path.is_empty();
+    }
+
+    pub fn stop (&mut self) {
+        self.path.clear();
+        self.known_hostiles.clear();
+    }
+}
+
+/// Plots a path to the clicked tile and walks the player along it, one step per turn.
+pub struct TravelSystem;
+
+impl <'a> System<'a> for TravelSystem {
+    type SystemData = (
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, Hostile>,
+        ReadStorage<'a, BlocksTile>,
+        WriteStorage<'a, Position>,
+        Read<'a, Map>,
+        Read<'a, SpatialIndex>,
+        Read<'a, Camera>,
+        Read<'a, Visibility>,
+        Read<'a, CurrentInput>,
+        Read<'a, DeltaTime>,
+        Write<'a, Turn>,
+        Write<'a, Travel>,
+        Write<'a, GameLog>,
+        Read<'a, SharedStrings>,
+    );
+
+    fn run (&mut self, (player_tag, hostiles, blockers, mut positions, map, spatial_index, camera, visibility, current_input, delta, mut turn, mut travel, mut game_log, strings) : Self::SystemData) {
+        use specs::Join;
+
+        let mut player = Option::None;
+        for (_player, position) in (&player_tag, &positions).join() {
+            player = Option::from((position.x, position.y));
+        }
+        let player_position = match player {
+            Some(position) => position,
+            None => return,
+        };
+
+        let hostiles_in_view : Vec<Entity> = spatial_index.entities_in_radius(player_position, SIGHT_RADIUS).into_iter()
+            .filter(|entity| hostiles.contains(*entity))
+            .filter(|entity| positions.get(*entity).is_some_and(|position| visibility.is_visible((position.x, position.y))))
+            .collect();
+
+        // pressing a key takes back control
+        if current_input.key.is_some() {
+            travel.stop();
+        }
+
+        let clicked_map = current_input.left_click && camera.mode == CameraMode::Follow
+            && camera.viewport.point_in_rect(Point::from_tuple(current_input.mouse_pos));
+        if clicked_map {
+            let target = camera.screen_to_map(current_input.mouse_pos);
+            travel.stop();
+            if target != player_position {
+                match find_path(&map, &spatial_index, &blockers, player_position, target) {
+                    Some(path) => {
+                        travel.path = path.into_iter().collect();
+                        travel.known_hostiles = hostiles_in_view.clone();
+                        // take the first step right away
+                        travel.since_last_step = STEP_DELAY;
+                    },
+                    None => game_log.push(LogCategory::System, strings.get("log.no_path"), turn.0),
+                }
+            }
+        }
+
+        if !travel.is_travelling() {
+            return;
+        }
+
+        if hostiles_in_view.iter().any(|hostile| !travel.known_hostiles.contains(hostile)) {
+            travel.stop();
+            game_log.push(LogCategory::Status, strings.get("log.hostile_stops_travel"), turn.0);
+            return;
+        }
+
+        travel.since_last_step += delta.0;
+        if travel.since_last_step < STEP_DELAY {
+            return;
+        }
+        travel.since_last_step = 0.0;
+
+        let next = match travel.path.pop_front() {
+            Some(next) => next,
+            None => return,
+        };
+        if spatial_index.is_blocked(next, &blockers) {
+            travel.stop();
+            game_log.push(LogCategory::Status, strings.get("log.path_blocked"), turn.0);
+            return;
+        }
+        for (_player, position) in (&player_tag, &mut positions).join() {
+            position.x = next.0;
+            position.y = next.1;
+        }
+        turn.0 += 1;
+    }
+}
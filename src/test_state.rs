@@ -1,27 +1,44 @@
 use bracket_lib::prelude::BTerm;
-use bracket_lib::prelude::VirtualKeyCode;
 use bracket_lib::prelude::DrawBatch;
 use bracket_lib::prelude::render_draw_buffer;
 use bracket_lib::prelude::RGB;
 use bracket_lib::prelude::Rect;
 
 //use specs::{Dispatcher, World, Builder};
-use specs::prelude::{World, WorldExt, Dispatcher, Builder};
+use specs::prelude::{World, WorldExt, Dispatcher, Builder, Join};
 
-use crate::state::{StateAction, State, CurrentInput, DeltaTime};
-use crate::components::basic::{Position, Renderable, Inventory, Currency, Actor, Light, ColorLerp, CycleAnimation};
-use crate::components::tag::PlayerTag;
-use crate::components::gui::{PlayerCard, Panel, Justification};
+use crate::state::{StateAction, State, CurrentInput, DeltaTime, Turn, WorldAction, StateResult, ScreenSize};
+use crate::components::basic::{Position, Renderable, Inventory, Currency, Actor, Light, ColorLerp, CycleAnimation, Equipable, Consumable};
+use crate::components::tag::{PlayerTag, BlocksTile, Hostile};
+use crate::components::gui::{PlayerCard, MessageLogCard, Panel, Justification};
 
 use crate::systems::render::{RenderSystem, GUIRenderSystem};
 use crate::systems::actor::PlayerMoveSystem;
 use crate::systems::player::PickUpSystem;
 use crate::systems::gui::GUIUpdate;
 use crate::systems::lighting::LightingSystem;
-use crate::level_generation::map::{Map, MapType};
+use crate::level_generation::map::{Map, SeedCode, GENERATOR_VERSION};
 use crate::systems::level::LevelGenSystem;
-use crate::systems::render::ObjectShader;
+use crate::systems::render::{ObjectShader, RenderLayer};
+use crate::systems::spatial::SpatialIndexSystem;
+use crate::systems::travel::TravelSystem;
+use crate::systems::sight::SightSystem;
+use crate::systems::explore::{ExploreSystem, Exploration};
 use crate::systems::animation::AnimationSystem;
+use crate::systems::daylight::{DaylightSystem, TimeOfDay};
+use crate::systems::camera::{Camera, CameraSystem, CameraMode};
+use crate::config::Settings;
+use crate::tileset::SpriteMap;
+use crate::game_log::{GameLog, LogCategory};
+use crate::log_viewer_state::LogViewerState;
+use crate::inventory_state::InventoryState;
+use crate::item::ItemBuilder;
+use std::sync::Arc;
+use std::fs;
+use crate::raw::{RawMaster, SharedRaws, RawError, RawWatcher, ItemType};
+use crate::game_over_state::{GameOverState, GameSummary, Ending};
+use crate::input::{KeyBindings, Command};
+use crate::locale::SharedStrings;
 
 pub struct TestState <'a, 'b>{
     world : World,
@@ -29,31 +46,61 @@ pub struct TestState <'a, 'b>{
     render_dispatcher : Dispatcher<'a, 'b>,
     gui_render_dispatcher  : Dispatcher<'a, 'b>,
     screen_size : (u32,u32),
+    seed : SeedCode,
+    /// Stats chosen during character creation, given to the player on init.
+    player_actor : Actor,
+    depth : u32,
+    /// Watches the raw files for changes while debugging.
+    raw_watcher : Option<RawWatcher>,
 }
 
+/// File the seed code is written to by `Command::CopySeed`.
+const SEED_FILE : &str = "seed.txt";
+/// Most raw problems listed in the message log after a reload; the rest only go to the log file.
+const MAX_REPORTED_PROBLEMS : usize = 5;
+
 impl <'a, 'b> TestState <'a, 'b> {
-    pub fn new (ctx : &mut BTerm) -> Self {
+    pub fn new (ctx : &mut BTerm, settings : &Settings, raws : SharedRaws, strings : SharedStrings, sprites : SpriteMap, seed : SeedCode, player_actor : Actor) -> Self {
         let mut world = World::new();
         world.register::<Position>();
         world.register::<Renderable>();
         world.register::<Inventory>();
         world.register::<Currency>();
+        world.register::<Equipable>();
+        world.register::<Consumable>();
         world.register::<Actor>();
         world.register::<PlayerCard>();
         world.register::<Panel>();
+        world.register::<MessageLogCard>();
         world.register::<Light>();
         world.register::<ColorLerp>();
         world.register::<CycleAnimation>();
+        world.register::<BlocksTile>();
+        world.register::<Hostile>();
 
         world.insert(DeltaTime(0.0));
         world.insert(CurrentInput::default());
+        world.insert(Turn(0));
+        world.insert(ScreenSize(ctx.get_char_size().0, ctx.get_char_size().1));
+        world.insert(GameLog::default());
+        world.insert(Exploration::new(settings.other.auto_explore_pickup));
+        world.insert(TimeOfDay::new(&settings.graphical.daylight));
+        world.insert(raws);
+        world.insert(strings);
+        world.insert(sprites);
+        world.insert(Camera::new(ctx.get_char_size()));
+
+        let (key_bindings, problems) = KeyBindings::from_config(&settings.keybindings);
+        for problem in problems.iter() {
+            warn!("Key bindings: {}", problem);
+        }
+        world.insert(key_bindings);
 
-        let seed = String::from("adsfasds");
-<<<<<<< HEAD
-        let mut map = Map::new(100, 100, seed, MapType::MushroomCavern, RGB::from_f32(0.0, 0.0, 0.1));
-=======
-        let mut map = Map::new(100, 100, seed, MapType::Cavern, RGB::from_f32(0.0, 0.0, 0.0));
->>>>>>> animation
+        if !seed.is_current() {
+            warn!("Seed code {} is from version {} of the level generators, not {}; the level may differ", seed, seed.version, GENERATOR_VERSION);
+        }
+        info!("Generating level from seed code {}", seed);
+        let mut map = Map::new(100, 100, seed.seed.clone(), seed.map_type, RGB::from_f32(0.0, 0.0, 0.1));
         map.generate();
         world.insert(map);
         
@@ -66,9 +113,15 @@ impl <'a, 'b> TestState <'a, 'b> {
 
         let mut update_dispatcher = specs::DispatcherBuilder::new()
                 .with(PlayerMoveSystem, "move_system", &[])
-                .with(PickUpSystem, "pickup_system", &[])
-                .with(GUIUpdate, "gui_update", &[])
+                .with(SightSystem, "sight_system", &["move_system"])
+                .with(TravelSystem, "travel_system", &["move_system", "sight_system"])
+                .with(ExploreSystem, "explore_system", &["move_system", "sight_system", "travel_system"])
+                .with(PickUpSystem, "pickup_system", &["explore_system"])
+                .with(GUIUpdate::new(ctx.get_char_size()), "gui_update", &[])
                 .with(AnimationSystem, "animation_update", &[])
+                .with(CameraSystem, "camera_update", &["move_system", "travel_system", "explore_system", "gui_update"])
+                .with(SpatialIndexSystem::default(), "spatial_index", &["move_system", "travel_system", "explore_system", "pickup_system"])
+                .with(DaylightSystem, "daylight_update", &["move_system", "travel_system", "explore_system", "pickup_system"])
                 .build();
         update_dispatcher.setup(&mut world);
 
@@ -93,39 +146,257 @@ impl <'a, 'b> TestState <'a, 'b> {
             render_dispatcher : render_dispatcher,
             gui_render_dispatcher : gui_render_dispatcher,
             screen_size : ctx.get_char_size(),
+            seed : seed,
+            player_actor : player_actor,
+            depth : 1,
+            raw_watcher : if settings.development.debug { Option::from(RawWatcher::new()) } else { Option::None },
+        }
+    }
+}
+
+impl <'a, 'b> TestState <'a, 'b> {
+    /// Returns the summary of the game if the player has died. Whatever killed the player logs the
+    /// last damage message, which becomes the cause of death. Nothing wins the game yet.
+    fn check_game_over (&self) -> Option<GameSummary> {
+        let player_tag = self.world.read_storage::<PlayerTag>();
+        let actors = self.world.read_storage::<Actor>();
+        let inventories = self.world.read_storage::<Inventory>();
+        let (actor, inventory) = match (&player_tag, &actors, &inventories).join().next() {
+            Some((_player, actor, inventory)) => (actor, inventory),
+            None => return Option::None,
+        };
+        if actor.current_health > 0 {
+            return Option::None;
+        }
+        let game_log = self.world.read_resource::<GameLog>();
+        let cause = game_log.entries.iter().rev()
+            .find(|entry| entry.category == LogCategory::Damage)
+            .map(|entry| entry.text.clone())
+            .unwrap_or_else(|| self.world.read_resource::<SharedStrings>().get("game_over.unknown_cause").to_string());
+        return Option::from(GameSummary {
+            ending : Ending::Died(cause),
+            seed : self.seed.to_string(),
+            depth : self.depth,
+            gold : inventory.money,
+            turns : self.world.read_resource::<Turn>().0,
+        });
+    }
+
+    /// Reloads the raw files and updates the items in the world to match. Problems are reported in
+    /// the message log; if the raws can't be read at all the old ones are kept.
+    fn reload_raws (&mut self) {
+        let strings = SharedStrings::clone(&self.world.read_resource::<SharedStrings>());
+        let mut raws = RawMaster::empty();
+        let result = raws.load_raws();
+        let mut messages = Vec::new();
+        let mut reloaded = true;
+        match result {
+            Ok(_v) => messages.push(strings.get("log.reloaded_raws").to_string()),
+            Err(RawError::Invalid(problems)) => {
+                for problem in problems.iter() {
+                    error!("{}", problem);
+                }
+                messages.push(strings.format("log.reloaded_raws_with_problems", &[&problems.len()]));
+                for problem in problems.iter().take(MAX_REPORTED_PROBLEMS) {
+                    messages.push(problem.to_string());
+                }
+                if problems.len() > MAX_REPORTED_PROBLEMS {
+                    messages.push(strings.format("log.more_problems", &[&(problems.len() - MAX_REPORTED_PROBLEMS)]));
+                }
+            },
+            Err(e) => {
+                error!("{}", e);
+                messages.push(strings.format("log.kept_old_raws", &[&e]));
+                reloaded = false;
+            },
+        }
+        if reloaded {
+            *self.world.write_resource::<SharedRaws>() = Arc::new(raws);
+            for id in ItemBuilder::refresh_items(&self.world) {
+                messages.push(strings.format("log.item_not_in_raws", &[&id]));
+            }
+        }
+
+        let turn = self.world.read_resource::<Turn>().0;
+        let mut game_log = self.world.write_resource::<GameLog>();
+        for message in messages {
+            game_log.push(LogCategory::System, message, turn);
         }
     }
+
+    /// Shows the seed code in the message log and writes it to a file, so it can be pasted into a
+    /// bug report.
+    fn copy_seed (&mut self) {
+        let strings = SharedStrings::clone(&self.world.read_resource::<SharedStrings>());
+        let message = match fs::write(SEED_FILE, format!("{}\n", self.seed)) {
+            Ok(_v) => strings.format("log.seed_code_saved", &[&self.seed, &SEED_FILE]),
+            Err(e) => {
+                error!("Unable to write {} : {}", SEED_FILE, e);
+                strings.format("log.seed_code", &[&self.seed])
+            },
+        };
+        info!("Seed code: {}", self.seed);
+        let turn = self.world.read_resource::<Turn>().0;
+        self.world.write_resource::<GameLog>().push(LogCategory::System, message, turn);
+    }
 }
 
 impl <'a, 'b> State for TestState <'a ,'b> {
 
     fn init (&mut self) {
+        let player_renderable = Renderable::new_from_sprite(&self.world.read_resource::<SpriteMap>(), "player", RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background).with_layer(RenderLayer::Player);
         self.world.create_entity()
             .with(Position::new(0, 0))
             .with(PlayerTag)
             .with(Inventory::new())
-            .with(Renderable::new(64, RGB::from_f32(1.0, 1.0, 1.0), RGB::from_f32(0.0, 0.0, 0.0), ObjectShader::Foreground, ObjectShader::Background))
-<<<<<<< HEAD
+            .with(player_renderable)
             .with(Light::new(10, 1.0, RGB::from_f32(0.75, 0.53, 0.0)))
-=======
-            .with(Light::new(5, 1.0, RGB::from_f32(1.0, 1.0, 1.0)))
->>>>>>> animation
-            .with(Actor::new())
+            .with(self.player_actor.clone())
+            .with(BlocksTile)
             .build();
 
         let player_card_bounds = Rect::with_size(0, 0, self.screen_size.0/4, self.screen_size.1);
+        let mut player_card_panel = Panel::new(player_card_bounds, true, Justification::RIGHT, Option::None);
+        player_card_panel.justify(Justification::RIGHT, Rect::with_size(0, 0, self.screen_size.0, self.screen_size.1));
 
         self.world.create_entity()
-            .with(Panel::new(player_card_bounds, true, Justification::RIGHT, Option::None))
+            .with(player_card_panel)
             .with(PlayerCard::new())
             .build();
 
+        let log_bounds = Rect::with_size(0, 0, self.screen_size.0, 6);
+        let mut log_panel = Panel::new(log_bounds, true, Justification::BOTTOM, Option::None);
+        log_panel.justify(Justification::BOTTOM, Rect::with_size(0, 0, self.screen_size.0 as i32 - player_card_bounds.width(), self.screen_size.1 as i32));
+
+        self.world.create_entity()
+            .with(log_panel)
+            .with(MessageLogCard::default())
+            .build();
+
+        {
+        let strings = self.world.read_resource::<SharedStrings>();
+        let mut game_log = self.world.write_resource::<GameLog>();
+        game_log.push(LogCategory::Discovery, strings.get(&format!("log.enter.{}", self.seed.map_type.code())), 0);
+        game_log.push(LogCategory::System, strings.format("log.seed_code", &[&self.seed]), 0);
+        if !self.seed.is_current() {
+            game_log.push(LogCategory::System, strings.get("log.different_version"), 0);
+        }
+        }
+
+        {
+        let map = self.world.read_resource::<Map>();
+        let mut camera = self.world.write_resource::<Camera>();
+        camera.fit_viewport(self.world.read_storage::<Panel>().join());
+        camera.center_on((0, 0), (map.width, map.height));
+        }
+
         info!("Initialized state");
     }
 
     fn on_enter (&mut self) {}
 
-    fn update (&mut self, ctx : &mut BTerm, input : CurrentInput, delta_time : DeltaTime) -> StateAction {
+    fn on_resume (&mut self, result : StateResult) {
+        let action = match result {
+            StateResult::World(action) => action,
+            _ => return,
+        };
+        let player = match (&self.world.entities(), &self.world.read_storage::<PlayerTag>()).join().next() {
+            Some((entity, _player)) => entity,
+            None => return,
+        };
+        let turn = self.world.read_resource::<Turn>().0;
+        let strings = SharedStrings::clone(&self.world.read_resource::<SharedStrings>());
+
+        match action {
+            WorldAction::PlayerUseItem(id) => {
+                let item = match self.world.read_resource::<SharedRaws>().get_item(id) {
+                    Some(item) => item.clone(),
+                    None => return,
+                };
+                if !self.world.write_storage::<Inventory>().get_mut(player).is_some_and(|inventory| inventory.remove_item(id)) {
+                    return;
+                }
+                let mut effects = item.potion.as_ref().and_then(|potion| potion.effects.clone()).unwrap_or_default();
+                effects.extend(item.consumable.as_ref().and_then(|consumable| consumable.effects.clone()).unwrap_or_default());
+                let key = if item.item_type == ItemType::Potion { "log.drink" } else { "log.use" };
+                let mut game_log = self.world.write_resource::<GameLog>();
+                let item_name = strings.item_name(&item);
+                game_log.push(LogCategory::Status, strings.format(key, &[&item_name]), turn);
+                for effect in effects.iter() {
+                    match effect.as_str() {
+                        "heal" => {
+                            match self.world.write_storage::<Actor>().get_mut(player) {
+                                Some(actor) => {
+                                    actor.current_health = (actor.current_health + actor.max_health / 4).min(actor.max_health);
+                                    game_log.push(LogCategory::Status, strings.get("log.healed"), turn);
+                                },
+                                None => {},
+                            }
+                        },
+                        "poison" => {
+                            match self.world.write_storage::<Actor>().get_mut(player) {
+                                Some(actor) => {
+                                    game_log.push(LogCategory::Damage, strings.format("log.poisoned", &[&item_name]), turn);
+                                    if actor.take_damage(actor.max_health / 4) {
+                                        game_log.push(LogCategory::Damage, strings.format("log.killed_by_poison", &[&item_name]), turn);
+                                    }
+                                },
+                                None => {},
+                            }
+                        },
+                        _ => {
+                            warn!("Unknown item effect \"{}\" on \"{}\"", effect, item.id);
+                        },
+                    }
+                }
+            },
+            WorldAction::PlayerEquipItem(id) => {
+                let name = match self.world.read_resource::<SharedRaws>().get_item(id) {
+                    Some(item) => strings.item_name(item),
+                    None => return,
+                };
+                match self.world.write_storage::<Inventory>().get_mut(player) {
+                    Some(inventory) => inventory.equipped = Option::from(id),
+                    None => return,
+                }
+                self.world.write_resource::<GameLog>().push(LogCategory::Status, strings.format("log.equip", &[&name]), turn);
+            },
+            WorldAction::PlayerDropItem(id) => {
+                let name = match self.world.read_resource::<SharedRaws>().get_item(id) {
+                    Some(item) => strings.item_name(item),
+                    None => return,
+                };
+                if !self.world.write_storage::<Inventory>().get_mut(player).is_some_and(|inventory| inventory.remove_item(id)) {
+                    return;
+                }
+                let position = match self.world.read_storage::<Position>().get(player) {
+                    Some(position) => (position.x, position.y),
+                    None => return,
+                };
+                ItemBuilder::build_item_with_id(self.world.create_entity(), id, position);
+                self.world.write_resource::<GameLog>().push(LogCategory::Pickup, strings.format("log.drop", &[&name]), turn);
+            },
+            _ => return,
+        }
+        self.world.write_resource::<Turn>().0 += 1;
+    }
+
+    fn on_resize (&mut self, screen_size : (u32, u32)) {
+        self.screen_size = screen_size;
+        *self.world.write_resource::<ScreenSize>() = ScreenSize(screen_size.0, screen_size.1);
+        self.world.write_resource::<Camera>().resize(screen_size);
+    }
+
+    fn update (&mut self, ctx : &mut BTerm, mut input : CurrentInput, delta_time : DeltaTime) -> StateAction {
+        input.command = self.world.read_resource::<KeyBindings>().command(&input);
+        let command = input.command;
+        let raws_changed = match &mut self.raw_watcher {
+            Some(raw_watcher) => raw_watcher.poll(delta_time.0) || command == Option::from(Command::ReloadRaws),
+            None => false,
+        };
+        if raws_changed {
+            self.reload_raws();
+        }
         {
         let mut delta = self.world.write_resource::<DeltaTime>();
         *delta = delta_time;
@@ -134,15 +405,50 @@ impl <'a, 'b> State for TestState <'a ,'b> {
         let mut current_input = self.world.write_resource::<CurrentInput>();
         *current_input = input;
         }
-        self.update_dispatcher.dispatch(&mut self.world);
+        // back leaves look mode rather than the game
+        let looking = self.world.read_resource::<Camera>().mode == CameraMode::Look;
+        self.update_dispatcher.dispatch(&self.world);
         self.world.maintain();
-        match ctx.key {
+
+        match self.check_game_over() {
+            Some(summary) => {
+                let raws = SharedRaws::clone(&self.world.read_resource::<SharedRaws>());
+                let strings = SharedStrings::clone(&self.world.read_resource::<SharedStrings>());
+                return StateAction::Switch(Box::new(GameOverState::new(summary, raws, strings)), Option::from("Game over.".to_string()));
+            },
+            None => {},
+        }
+
+        match command {
             None => {return StateAction::NoAction},
-            Some(key) => {
+            Some(command) => {
 
-                match key {
-                    VirtualKeyCode::Escape => {return StateAction::Exit},
-                    VirtualKeyCode::F2 => {
+                match command {
+                    Command::Back if !looking => {
+                        let raws = SharedRaws::clone(&self.world.read_resource::<SharedRaws>());
+                        return StateAction::PopWithResult(StateResult::Raws(raws), Option::from("Returned to the main menu.".to_string()));
+                    },
+                    Command::OpenInventory if !looking => {
+                        let player_tag = self.world.read_storage::<PlayerTag>();
+                        let inventories = self.world.read_storage::<Inventory>();
+                        match (&player_tag, &inventories).join().next() {
+                            Some((_player, inventory)) => {
+                                let state = InventoryState::new(&self.world.read_resource::<SharedRaws>(), SharedStrings::clone(&self.world.read_resource::<SharedStrings>()), &inventory.items, inventory.money, inventory.equipped);
+                                return StateAction::Push(Box::new(state), Option::from("Opened inventory.".to_string()));
+                            },
+                            None => {return StateAction::NoAction},
+                        }
+                    },
+                    Command::OpenMessageLog if !looking => {
+                        let entries = self.world.read_resource::<GameLog>().entries.clone();
+                        let strings = SharedStrings::clone(&self.world.read_resource::<SharedStrings>());
+                        return StateAction::Push(Box::new(LogViewerState::new(entries, strings)), Option::from("Opened message history.".to_string()));
+                    },
+                    Command::CopySeed => {
+                        self.copy_seed();
+                        return StateAction::NoAction
+                    },
+                    Command::Screenshot => {
                         info!("Screenshot");
                         ctx.screenshot("screenshots/screenshot.png");
                         return StateAction::NoAction
@@ -154,7 +460,7 @@ impl <'a, 'b> State for TestState <'a ,'b> {
     }
 
     fn render (&mut self, ctx : &mut BTerm) {
-      self.render_dispatcher.dispatch(&mut self.world);
+      self.render_dispatcher.dispatch(&self.world);
 
       let draw_result = render_draw_buffer(ctx);
       match draw_result{
@@ -164,7 +470,7 @@ impl <'a, 'b> State for TestState <'a ,'b> {
             },
       }
       //this is done so the gui is rendered on top of everythign else
-      self.gui_render_dispatcher.dispatch(&mut self.world);
+      self.gui_render_dispatcher.dispatch(&self.world);
       let draw_result = render_draw_buffer(ctx);
       match draw_result{
             Ok(_v) => {},
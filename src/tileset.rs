@@ -0,0 +1,157 @@
+//! Bitmap tilesets and the mapping from named sprites to glyphs.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum TilesetKind {
+    /// A 16x16 sheet laid out like code page 437. Sprites resolve to their CP437 code.
+    Cp437,
+    /// A sheet of pictures. Sprites resolve through the tileset's sprite map.
+    Graphical,
+}
+
+/// A bitmap tileset in the `assets` directory.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Tileset {
+    pub name : String,
+    pub file : String,
+    pub tile_width : u32,
+    pub tile_height : u32,
+    pub kind : TilesetKind,
+    /// JSON file in `assets` mapping sprite names to tile indices. Only used by graphical tilesets.
+    #[serde(default)]
+    pub sprites : Option<String>,
+}
+
+impl Tileset {
+    pub fn new (name : &str, file : &str, tile_width : u32, tile_height : u32, kind : TilesetKind) -> Self {
+        Tileset {
+            name : name.to_string(),
+            file : file.to_string(),
+            tile_width : tile_width,
+            tile_height : tile_height,
+            kind : kind,
+            sprites : Option::None,
+        }
+    }
+}
+
+/// The tilesets shipped in `assets`.
+pub fn default_tilesets () -> Vec<Tileset> {
+    return vec![
+        Tileset::new("terminal", "terminal.png", 8, 12, TilesetKind::Cp437),
+        Tileset::new("vga", "vga8x16.png", 8, 16, TilesetKind::Cp437),
+        Tileset::new("square", "cp437_20x20.png", 20, 20, TilesetKind::Cp437),
+    ];
+}
+
+/// Finds a tileset by name, falling back on the first one if it doesn't exist.
+pub fn find_tileset (tilesets : &[Tileset], name : &str) -> Tileset {
+    for tileset in tilesets {
+        if tileset.name == name {
+            return tileset.clone();
+        }
+    }
+    warn!("No tileset named \"{}\"; falling back on \"{}\"", name, tilesets[0].name);
+    return tilesets[0].clone();
+}
+
+/// Resolves sprite names to glyphs for the active tileset.
+pub struct SpriteMap {
+    pub kind : TilesetKind,
+    graphical : HashMap<String, u16>,
+}
+
+impl Default for SpriteMap {
+    fn default() -> Self {
+        SpriteMap {
+            kind : TilesetKind::Cp437,
+            graphical : HashMap::new(),
+        }
+    }
+}
+
+impl SpriteMap {
+    /// Builds the sprite map for a tileset, loading its sprite file if it is graphical.
+    pub fn new (tileset : &Tileset) -> Self {
+        let mut graphical = HashMap::new();
+        if tileset.kind == TilesetKind::Graphical {
+            match &tileset.sprites {
+                Some(file) => {
+                    let path = format!("assets/{}", file);
+                    match fs::read_to_string(&path) {
+                        Ok(t) => {
+                            match serde_json::from_str::<HashMap<String, u16>>(&t) {
+                                Ok(sprites) => graphical = sprites,
+                                Err(e) => error!("Error loading sprite map \"{}\" : {}", path, e),
+                            }
+                        },
+                        Err(e) => error!("Error reading sprite map \"{}\" : {}", path, e),
+                    }
+                },
+                None => {
+                    warn!("Graphical tileset \"{}\" has no sprite map; using CP437 codes", tileset.name);
+                },
+            }
+        }
+
+        SpriteMap {
+            kind : tileset.kind,
+            graphical : graphical,
+        }
+    }
+
+    /// Returns the glyph for a named sprite. Graphical tilesets fall back on the CP437 code
+    /// for sprites they don't define, and unknown names resolve to '?'.
+    pub fn resolve (&self, name : &str) -> u16 {
+        if self.kind == TilesetKind::Graphical {
+            match self.graphical.get(name) {
+                Some(idx) => return *idx,
+                None => {},
+            }
+        }
+        match cp437_sprite(name) {
+            Some(glyph) => glyph,
+            None => {
+                error!("Unknown sprite \"{}\"", name);
+                '?' as u16
+            },
+        }
+    }
+}
+
+/// CP437 codes for the built in sprite names.
+pub fn cp437_sprite (name : &str) -> Option<u16> {
+    let glyph = match name {
+        "player" => 64,
+        "floor" => 46,
+        "wall" => 219,
+        "wall_front" => 223,
+        "water" => 247,
+        "lava" => 247,
+        "grass_short" => 253,
+        "grass_tall" => 244,
+        "grass_tall_bent" => 245,
+        "mushroom_small" => 43,
+        "mushroom_large" => 6,
+        "mushroom_large_round" => 5,
+        "web_thin" => 15,
+        "web_thick" => 176,
+        "egg_sac" => 7,
+        "fire" => 30,
+        "camp_seat" => 61,
+        "tent_top_left" => 218,
+        "tent_top_center" => 196,
+        "tent_top_right" => 191,
+        "tent_bottom_left" => 198,
+        "tent_bottom_center" => 205,
+        "tent_bottom_right" => 181,
+        "potion" => 235,
+        "gem" => 4,
+        "coin" => 42,
+        _ => return Option::None,
+    };
+    return Option::from(glyph);
+}
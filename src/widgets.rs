@@ -0,0 +1,519 @@
+//! Reusable GUI widgets. Widgets draw into the bounds of a `Panel`; use `Panel::child` and
+//! `Panel::row` to lay them out inside a parent panel.
+
+use bracket_lib::prelude::{DrawBatch, Point, Rect, ColorPair, RGB, VirtualKeyCode};
+
+use crate::components::gui::{Panel, Justification};
+use crate::state::CurrentInput;
+
+pub const FRAME_FG : RGB = RGB { r : 1.0, g : 1.0, b : 1.0 };
+pub const FRAME_BG : RGB = RGB { r : 50.0 / 255.0, g : 50.0 / 255.0, b : 50.0 / 255.0 };
+pub const HIGHLIGHT_BG : RGB = RGB { r : 90.0 / 255.0, g : 90.0 / 255.0, b : 140.0 / 255.0 };
+
+/// Cuts a string down to the given number of characters.
+pub fn fit_text (text : &str, width : i32) -> String {
+    return text.chars().take(width.max(0) as usize).collect();
+}
+
+/// Draws the border of a decorated panel.
+pub fn draw_frame (draw_batch : &mut DrawBatch, panel : &Panel, double : bool) {
+    if !panel.decorations {
+        return;
+    }
+    let frame = Rect::with_size(panel.bounds.x1, panel.bounds.y1, panel.width(), panel.height() - 1);
+    if double {
+        draw_batch.draw_double_box(frame, ColorPair::new(FRAME_FG, FRAME_BG));
+    } else {
+        draw_batch.draw_box(frame, ColorPair::new(FRAME_FG, FRAME_BG));
+    }
+}
+
+/// Draws a panel as an opaque window with a double border and a title, covering whatever is beneath it.
+pub fn draw_window (draw_batch : &mut DrawBatch, panel : &Panel, title : &str) {
+    for y in panel.bounds.y1..panel.bounds.y2 {
+        for x in panel.bounds.x1..=panel.bounds.x2 {
+            draw_batch.set(Point::new(x, y), ColorPair::new(FRAME_FG, FRAME_BG), 32);
+        }
+    }
+    draw_frame(draw_batch, panel, true);
+    if !title.is_empty() {
+        let title = fit_text(&format!(" {} ", title), panel.width() - 1);
+        let x = panel.bounds.x1 + (panel.width() + 1 - title.chars().count() as i32) / 2;
+        draw_batch.print_color(Point::new(x, panel.bounds.y1), title, ColorPair::new(FRAME_FG, FRAME_BG));
+    }
+}
+
+/// Draws a horizontal bar filled to `current / max`, using shaded glyphs for partially filled cells.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_bar_horizontal (draw_batch : &mut DrawBatch, position : (i32,i32), width : i32, current : i32, max : i32, fg : RGB, bg : RGB, with_decoration : bool) {
+    let percent = current as f32 / max as f32;
+    let fill_width = (percent * width as f32) as i32;
+
+    let fill_width_float : f32 = percent * width as f32;
+    let mut offset = 0;
+    if with_decoration {
+        offset = 1;
+        draw_batch.set(Point::from((position.0,position.1)), ColorPair::new(RGB::named(bracket_lib::prelude::WHITE), RGB::named(bracket_lib::prelude::BLACK)), 180);
+    }
+
+    for x in 0..(width + offset) {
+        let difference = fill_width_float - x as f32;
+        if (x == 0 && x < fill_width) || (x > 0 && x <= fill_width) || (x == 0 && difference > 0.0) {
+            if difference >= 1.0 { // 100%
+                draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 219);
+            } else if difference < 1.00 && difference > 0.50 { // <100%
+                draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 178);
+            } else if difference <= 0.50 && difference > 0.25 { // <50%
+                draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 177);
+            } else if difference <= 0.25 && difference > 0.0 { // <25%
+                draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 176);
+            }
+        } else {
+           draw_batch.set(Point::from((position.0 + x + offset, position.1)), ColorPair::new(fg, bg), 249);
+        }
+    }
+
+    if with_decoration {
+        draw_batch.set(Point::from((position.0 + (width + offset), position.1)), ColorPair::new(RGB::named(bracket_lib::prelude::WHITE), bg), 195);
+    }
+}
+
+/// A single line of text.
+pub struct Label {
+    pub text : String,
+    pub color : RGB,
+}
+
+impl Label {
+    pub fn new<S : ToString> (text : S) -> Self {
+        Label {
+            text : text.to_string(),
+            color : FRAME_FG,
+        }
+    }
+
+    pub fn with_color (mut self, color : RGB) -> Self {
+        self.color = color;
+        return self;
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, area : &Panel) {
+        let text = fit_text(&self.text, area.width());
+        draw_batch.print_color(Point::new(area.bounds.x1, area.bounds.y1), text, ColorPair::new(self.color, FRAME_BG));
+    }
+}
+
+/// A bar showing how full a value is, optionally prefixed with a caption.
+pub struct ProgressBar {
+    pub caption : String,
+    pub current : i32,
+    pub max : i32,
+    pub fg : RGB,
+    pub bg : RGB,
+}
+
+impl ProgressBar {
+    pub fn new<S : ToString> (caption : S, current : i32, max : i32, fg : RGB, bg : RGB) -> Self {
+        ProgressBar {
+            caption : caption.to_string(),
+            current : current,
+            max : max,
+            fg : fg,
+            bg : bg,
+        }
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, area : &Panel) {
+        let caption_width = self.caption.chars().count() as i32;
+        if caption_width > 0 {
+            draw_batch.print_color(Point::new(area.bounds.x1, area.bounds.y1), fit_text(&self.caption, area.width()), ColorPair::new(FRAME_FG, FRAME_BG));
+        }
+        // the decorations take up one cell on either side of the bar
+        let width = area.width() - caption_width - 2;
+        if width > 0 {
+            draw_bar_horizontal(draw_batch, (area.bounds.x1 + caption_width, area.bounds.y1), width, self.current, self.max.max(1), self.fg, self.bg, true);
+        }
+    }
+}
+
+/// A vertical list of items with a selection that follows the keyboard and mouse.
+pub struct ListView {
+    pub items : Vec<String>,
+    pub selected : usize,
+    /// Index of the first visible item.
+    pub scroll : usize,
+    /// Where the mouse was last update, so a resting mouse doesn't fight the keyboard.
+    last_mouse : (i32, i32),
+}
+
+impl ListView {
+    pub fn new (items : Vec<String>) -> Self {
+        ListView {
+            items : items,
+            selected : 0,
+            scroll : 0,
+            last_mouse : (-1, -1),
+        }
+    }
+
+    pub fn select_next (&mut self) {
+        if self.selected + 1 < self.items.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous (&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Returns the index of the item under the given screen position.
+    pub fn item_at (&self, area : &Panel, position : (i32, i32)) -> Option<usize> {
+        if !area.point_in_panel(position) {
+            return Option::None;
+        }
+        let idx = self.scroll + (position.1 - area.bounds.y1) as usize;
+        if idx < self.items.len() {
+            return Option::from(idx);
+        }
+        return Option::None;
+    }
+
+    /// Moves the selection with Up/Down or the mouse. Returns the selected index when it is
+    /// activated with Enter or a left click.
+    pub fn update (&mut self, input : &CurrentInput, area : &Panel) -> Option<usize> {
+        if self.items.is_empty() {
+            return Option::None;
+        }
+        let mut activated = Option::None;
+        match input.key {
+            Some(VirtualKeyCode::Up) => self.select_previous(),
+            Some(VirtualKeyCode::Down) => self.select_next(),
+            Some(VirtualKeyCode::Return) => activated = Option::from(self.selected),
+            _ => {},
+        }
+        let mouse_moved = input.mouse_pos != self.last_mouse;
+        self.last_mouse = input.mouse_pos;
+        match self.item_at(area, input.mouse_pos) {
+            Some(idx) if mouse_moved || input.left_click => {
+                self.selected = idx;
+                if input.left_click {
+                    activated = Option::from(idx);
+                }
+            },
+            _ => {},
+        }
+        self.selected = self.selected.min(self.items.len() - 1);
+        self.scroll_to_selection(area.height() as usize);
+        return activated;
+    }
+
+    fn scroll_to_selection (&mut self, visible : usize) {
+        if visible == 0 {
+            return;
+        }
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + visible {
+            self.scroll = self.selected + 1 - visible;
+        }
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, area : &Panel) {
+        let visible = area.height().max(0) as usize;
+        for (row, idx) in (self.scroll..self.items.len()).take(visible).enumerate() {
+            let bg = if idx == self.selected { HIGHLIGHT_BG } else { FRAME_BG };
+            let y = area.bounds.y1 + row as i32;
+            for x in area.bounds.x1..area.bounds.x2 {
+                draw_batch.set_bg(Point::new(x, y), bg);
+            }
+            draw_batch.print_color(Point::new(area.bounds.x1, y), fit_text(&self.items[idx], area.width()), ColorPair::new(FRAME_FG, bg));
+        }
+    }
+}
+
+/// Colored lines of text that can be scrolled through.
+pub struct ScrollView {
+    pub lines : Vec<(String, RGB)>,
+    /// How many lines the view is scrolled away from its anchor.
+    pub scroll : usize,
+    /// Keep the last line at the bottom of the view instead of the first line at the top.
+    pub anchor_bottom : bool,
+}
+
+impl ScrollView {
+    pub fn new (lines : Vec<(String, RGB)>) -> Self {
+        ScrollView {
+            lines : lines,
+            scroll : 0,
+            anchor_bottom : false,
+        }
+    }
+
+    pub fn anchored_bottom (mut self) -> Self {
+        self.anchor_bottom = true;
+        return self;
+    }
+
+    pub fn with_scroll (mut self, scroll : usize) -> Self {
+        self.scroll = scroll;
+        return self;
+    }
+
+    /// Furthest the view can be scrolled while still filling the given area.
+    pub fn max_scroll (&self, area : &Panel) -> usize {
+        return self.lines.len().saturating_sub(area.height().max(0) as usize);
+    }
+
+    /// Scrolls with PageUp/PageDown and the arrow keys.
+    pub fn update (&mut self, input : &CurrentInput, area : &Panel) {
+        let towards_start = match input.key {
+            Some(VirtualKeyCode::PageUp) | Some(VirtualKeyCode::Up) => Option::from(true),
+            Some(VirtualKeyCode::PageDown) | Some(VirtualKeyCode::Down) => Option::from(false),
+            _ => Option::None,
+        };
+        match towards_start {
+            Some(towards_start) => {
+                if towards_start == self.anchor_bottom {
+                    self.scroll += 1;
+                } else {
+                    self.scroll = self.scroll.saturating_sub(1);
+                }
+            },
+            None => {},
+        }
+        self.scroll = self.scroll.min(self.max_scroll(area));
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, area : &Panel) {
+        let visible = area.height().max(0) as usize;
+        let (start, end) = if self.anchor_bottom {
+            let end = self.lines.len().saturating_sub(self.scroll);
+            (end.saturating_sub(visible), end)
+        } else {
+            let start = self.scroll.min(self.lines.len());
+            (start, (start + visible).min(self.lines.len()))
+        };
+
+        // short content hugs the anchored edge
+        let mut y = area.bounds.y1;
+        if self.anchor_bottom {
+            y += (visible - (end - start)) as i32;
+        }
+        for (text, color) in self.lines[start..end].iter() {
+            draw_batch.print_color(Point::new(area.bounds.x1, y), fit_text(text, area.width()), ColorPair::new(*color, FRAME_BG));
+            y += 1;
+        }
+    }
+}
+
+/// A row of tab titles above a content area. Only the active tab's content is drawn by the caller.
+pub struct Tabs {
+    pub titles : Vec<String>,
+    pub active : usize,
+}
+
+impl Tabs {
+    pub fn new (titles : Vec<String>) -> Self {
+        Tabs {
+            titles : titles,
+            active : 0,
+        }
+    }
+
+    /// Area below the titles for the active tab's content.
+    pub fn content (&self, area : &Panel) -> Panel {
+        return area.child(Rect::with_size(0, 1, area.width(), area.height() - 1));
+    }
+
+    /// Screen areas taken up by each title.
+    fn title_areas (&self, area : &Panel) -> Vec<Panel> {
+        let mut areas = Vec::new();
+        let mut x = 0;
+        for title in self.titles.iter() {
+            let width = title.chars().count() as i32 + 2;
+            areas.push(area.child(Rect::with_size(x, 0, width, 1)));
+            x += width + 1;
+        }
+        return areas;
+    }
+
+    /// Switches tabs with Tab or by clicking a title. Returns true if the active tab changed.
+    pub fn update (&mut self, input : &CurrentInput, area : &Panel) -> bool {
+        if self.titles.is_empty() {
+            return false;
+        }
+        let previous = self.active;
+        if input.key == Some(VirtualKeyCode::Tab) {
+            if input.shift {
+                self.active = (self.active + self.titles.len() - 1) % self.titles.len();
+            } else {
+                self.active = (self.active + 1) % self.titles.len();
+            }
+        }
+        if input.left_click {
+            for (idx, title_area) in self.title_areas(area).iter().enumerate() {
+                if title_area.point_in_panel(input.mouse_pos) {
+                    self.active = idx;
+                }
+            }
+        }
+        return self.active != previous;
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, area : &Panel) {
+        for (idx, title_area) in self.title_areas(area).iter().enumerate() {
+            let bg = if idx == self.active { HIGHLIGHT_BG } else { FRAME_BG };
+            let text = fit_text(&format!(" {} ", self.titles[idx]), title_area.width());
+            draw_batch.print_color(Point::new(title_area.bounds.x1, title_area.bounds.y1), text, ColorPair::new(FRAME_FG, bg));
+        }
+    }
+}
+
+/// A single line the player can type into.
+pub struct TextInput {
+    pub text : String,
+    pub max_length : usize,
+}
+
+impl TextInput {
+    pub fn new (max_length : usize) -> Self {
+        TextInput {
+            text : String::new(),
+            max_length : max_length,
+        }
+    }
+
+    /// Types letters, digits and a few symbols, and deletes with Backspace. Returns true on Enter.
+    pub fn update (&mut self, input : &CurrentInput) -> bool {
+        match input.key {
+            Some(VirtualKeyCode::Return) => return true,
+            Some(VirtualKeyCode::Back) => {
+                self.text.pop();
+            },
+            Some(key) => {
+                match key_to_char(key, input.shift) {
+                    Some(c) if self.text.chars().count() < self.max_length => self.text.push(c),
+                    _ => {},
+                }
+            },
+            None => {},
+        }
+        return false;
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, area : &Panel) {
+        for x in area.bounds.x1..area.bounds.x2 {
+            draw_batch.set(Point::new(x, area.bounds.y1), ColorPair::new(FRAME_FG, HIGHLIGHT_BG), 32);
+        }
+        // keep the end of the text and the cursor visible
+        let visible = (area.width() - 1).max(0) as usize;
+        let skip = self.text.chars().count().saturating_sub(visible);
+        let text : String = self.text.chars().skip(skip).collect();
+        draw_batch.print_color(Point::new(area.bounds.x1, area.bounds.y1), format!("{}_", text), ColorPair::new(FRAME_FG, HIGHLIGHT_BG));
+    }
+}
+
+/// The character a key types, if any.
+pub fn key_to_char (key : VirtualKeyCode, shift : bool) -> Option<char> {
+    let letter = match key {
+        VirtualKeyCode::A => 'a', VirtualKeyCode::B => 'b', VirtualKeyCode::C => 'c', VirtualKeyCode::D => 'd',
+        VirtualKeyCode::E => 'e', VirtualKeyCode::F => 'f', VirtualKeyCode::G => 'g', VirtualKeyCode::H => 'h',
+        VirtualKeyCode::I => 'i', VirtualKeyCode::J => 'j', VirtualKeyCode::K => 'k', VirtualKeyCode::L => 'l',
+        VirtualKeyCode::M => 'm', VirtualKeyCode::N => 'n', VirtualKeyCode::O => 'o', VirtualKeyCode::P => 'p',
+        VirtualKeyCode::Q => 'q', VirtualKeyCode::R => 'r', VirtualKeyCode::S => 's', VirtualKeyCode::T => 't',
+        VirtualKeyCode::U => 'u', VirtualKeyCode::V => 'v', VirtualKeyCode::W => 'w', VirtualKeyCode::X => 'x',
+        VirtualKeyCode::Y => 'y', VirtualKeyCode::Z => 'z',
+        _ => ' ',
+    };
+    if letter != ' ' {
+        return Option::from(if shift { letter.to_ascii_uppercase() } else { letter });
+    }
+
+    let symbol = match key {
+        VirtualKeyCode::Key3 if shift => '#',
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => '0',
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => '1',
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => '2',
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => '3',
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => '4',
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => '5',
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => '6',
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => '7',
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => '8',
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => '9',
+        VirtualKeyCode::Space => ' ',
+        VirtualKeyCode::Minus if shift => '_',
+        VirtualKeyCode::Minus => '-',
+        VirtualKeyCode::Period => '.',
+        VirtualKeyCode::Slash => '/',
+        VirtualKeyCode::Backslash => '\\',
+        VirtualKeyCode::Semicolon if shift => ':',
+        _ => return Option::None,
+    };
+    return Option::from(symbol);
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ModalResult {
+    Pending,
+    Chosen(usize),
+    Cancelled,
+}
+
+/// A box centered over a container asking the player to pick one of several options.
+pub struct Modal {
+    pub title : String,
+    pub message : Vec<String>,
+    pub options : ListView,
+}
+
+impl Modal {
+    pub fn new<S : ToString> (title : S, message : Vec<String>, options : Vec<String>) -> Self {
+        Modal {
+            title : title.to_string(),
+            message : message,
+            options : ListView::new(options),
+        }
+    }
+
+    /// The panel the dialog occupies, centered inside the container.
+    pub fn panel (&self, container : Rect) -> Panel {
+        let longest = self.message.iter().chain(self.options.items.iter()).chain(std::iter::once(&self.title))
+            .map(|line| line.chars().count() as i32)
+            .max()
+            .unwrap_or(0);
+        let width = (longest + 4).min(container.width());
+        let height = (self.message.len() + self.options.items.len()) as i32 + 4;
+        let height = height.min(container.height());
+        let x = container.x1 + (container.width() - width) / 2;
+        let y = container.y1 + (container.height() - height) / 2;
+        return Panel::new(Rect::with_size(x, y, width, height), true, Justification::CENTER, Option::from(container));
+    }
+
+    fn options_area (&self, panel : &Panel) -> Panel {
+        let top = self.message.len() as i32 + 1;
+        return panel.child(Rect::with_size(0, top, panel.content().width(), self.options.items.len() as i32));
+    }
+
+    /// Enter or a click picks the selected option, Escape cancels.
+    pub fn update (&mut self, input : &CurrentInput, container : Rect) -> ModalResult {
+        if input.key == Some(VirtualKeyCode::Escape) {
+            return ModalResult::Cancelled;
+        }
+        let panel = self.panel(container);
+        let options_area = self.options_area(&panel);
+        match self.options.update(input, &options_area) {
+            Some(idx) => ModalResult::Chosen(idx),
+            None => ModalResult::Pending,
+        }
+    }
+
+    pub fn draw (&self, draw_batch : &mut DrawBatch, container : Rect) {
+        let panel = self.panel(container);
+        draw_window(draw_batch, &panel, &self.title);
+        for (row, line) in self.message.iter().enumerate() {
+            Label::new(line).draw(draw_batch, &panel.row(row as i32));
+        }
+        self.options.draw(draw_batch, &self.options_area(&panel));
+    }
+}